@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use log::info;
+use simple_logger::SimpleLogger;
+
+use ultralight_rs::Headless;
+
+fn main() {
+    SimpleLogger::new().init().unwrap();
+
+    let mut headless = Headless::builder().size(800, 480).build();
+
+    headless
+        .view
+        .load_html_and_wait(
+            &headless.renderer,
+            r#"
+            <html>
+                <head><title>Headless</title></head>
+                <body>Rendered without a window</body>
+            </html>"#,
+            Duration::from_secs(5),
+        )
+        .unwrap();
+    headless.tick();
+
+    info!(
+        "Loaded page titled '{}'",
+        Into::<String>::into(headless.view.title())
+    );
+}
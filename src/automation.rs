@@ -0,0 +1,298 @@
+//! Elements are resolved with JavaScript, interactions are then delivered as real
+//! mouse and keyboard events to the [View], like a user would.
+//!
+//! ```no_run
+//! # use ultralight_rs::View;
+//! # fn test(view: &mut View) -> anyhow::Result<()> {
+//! view.query("input[name=login]")?.expect("no login field").fill("admin")?;
+//! view.query("button[type=submit]")?.unwrap().click()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{anyhow, Result};
+
+use crate::helpers::js_string_literal;
+use crate::jsc::JSValue;
+use crate::{key_codes, modifiers, KeyEvent, MouseButton, MouseEvent, MouseEventType, View};
+
+/// Name of the array, on the JS global object, keeping track of the queried elements.
+const ELEMENTS_REGISTRY: &str = "__ultralight_rs_elements";
+
+/// Position and size of an element, in CSS pixels relative to the viewport.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BoundingBox {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl BoundingBox {
+    /// Center of the box.
+    pub fn center(&self) -> (f64, f64) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+}
+
+impl View {
+    /// Find the first element matching a CSS selector.
+    /// Returns `None` if nothing matches.
+    pub fn query(&mut self, selector: &str) -> Result<Option<ElementHandle<'_>>> {
+        let id = self
+            .evaluate_script(&query_script(selector))?
+            .as_number()
+            .map_err(|_| anyhow!("querySelector({}) didn't return an element id", selector))?;
+        if id < 0.0 {
+            Ok(None)
+        } else {
+            Ok(Some(ElementHandle {
+                view: self,
+                id: id as u32,
+            }))
+        }
+    }
+}
+
+/// Script registering the first element matching `selector`, returning its id or -1.
+fn query_script(selector: &str) -> String {
+    format!(
+        "(function() {{ \
+            var el = document.querySelector({}); \
+            if (!el) return -1; \
+            var r = window.{reg} || (window.{reg} = {{ next: 0, elements: new Map() }}); \
+            r.elements.set(r.next, el); \
+            return r.next++; \
+        }})()",
+        js_string_literal(selector),
+        reg = ELEMENTS_REGISTRY
+    )
+}
+
+/// Script calling `body` as the body of a function taking the registered element `id` as `el`.
+fn element_script(id: u32, body: &str) -> String {
+    format!(
+        "(function(el) {{ \
+            if (!el) throw new Error('element handle is no longer valid'); \
+            {} \
+        }})(window.{reg} && window.{reg}.elements.get({}))",
+        body,
+        id,
+        reg = ELEMENTS_REGISTRY
+    )
+}
+
+/// Script unregistering the element `id`, so the page can free it.
+fn release_script(id: u32) -> String {
+    format!(
+        "window.{reg} && window.{reg}.elements.delete({})",
+        id,
+        reg = ELEMENTS_REGISTRY
+    )
+}
+
+/// Events sent by a keyboard typing `c` : key down, character and key up.
+fn key_strokes(c: char) -> [KeyEvent; 3] {
+    let (virtual_key_code, shift) = match c {
+        'a'..='z' => (c.to_ascii_uppercase() as i32, false),
+        'A'..='Z' => (c as i32, true),
+        '0'..='9' => (c as i32, false),
+        ' ' => (key_codes::SPACE, false),
+        '\t' => (key_codes::TAB, false),
+        '\n' | '\r' => (key_codes::RETURN, false),
+        // No key for this character, it is only inserted by the char event
+        _ => (0, false),
+    };
+    let modifiers = if shift { modifiers::SHIFT } else { 0 };
+    let mut down = KeyEvent::raw_key_down(virtual_key_code);
+    let mut char = KeyEvent::char(if c == '\n' { '\r' } else { c });
+    let mut up = KeyEvent::key_up(virtual_key_code);
+    char.virtual_key_code = virtual_key_code;
+    for event in [&mut down, &mut char, &mut up].iter_mut() {
+        event.modifiers = modifiers;
+    }
+    [down, char, up]
+}
+
+/// A handle to a DOM element of a [View], obtained with [View::query].
+///
+/// The handle stays valid until the page is reloaded or navigated away,
+/// the page stops keeping the element alive when the handle is dropped.
+pub struct ElementHandle<'a> {
+    view: &'a mut View,
+    id: u32,
+}
+
+impl ElementHandle<'_> {
+    /// Evaluate `body` as the body of a function taking the element as `el`.
+    fn eval(&mut self, body: &str) -> Result<JSValue> {
+        self.view.evaluate_script(&element_script(self.id, body))
+    }
+
+    fn eval_string(&mut self, body: &str) -> Result<String> {
+        self.eval(body)?
+            .as_string()
+            .map(|s| s.to_string())
+            .map_err(|_| anyhow!("couldn't convert result to a string"))
+    }
+
+    /// Get the bounding box of the element, `None` if it isn't rendered.
+    pub fn bounding_box(&mut self) -> Result<Option<BoundingBox>> {
+        let rect = self.eval_string(
+            "if (el.getClientRects().length === 0) return ''; \
+            var r = el.getBoundingClientRect(); \
+            return [r.left, r.top, r.width, r.height].join(',');",
+        )?;
+        if rect.is_empty() {
+            return Ok(None);
+        }
+        let values = rect
+            .split(',')
+            .map(|v| v.parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(BoundingBox {
+            x: values[0],
+            y: values[1],
+            width: values[2],
+            height: values[3],
+        }))
+    }
+
+    /// Get the text content of the element.
+    pub fn text(&mut self) -> Result<String> {
+        self.eval_string("return el.textContent || '';")
+    }
+
+    /// Get the value of an attribute, `None` if the element doesn't have it.
+    pub fn attribute(&mut self, name: &str) -> Result<Option<String>> {
+        let value = self.eval(&format!(
+            "return el.getAttribute({});",
+            js_string_literal(name)
+        ))?;
+        if value.is_null() {
+            Ok(None)
+        } else {
+            value
+                .as_string()
+                .map(|s| Some(s.to_string()))
+                .map_err(|_| anyhow!("couldn't convert attribute {} to a string", name))
+        }
+    }
+
+    /// Scroll the element into view and click in its center with the left mouse button.
+    pub fn click(&mut self) -> Result<()> {
+        self.eval("el.scrollIntoView({ block: 'center', inline: 'center' });")?;
+        let bounds = self
+            .bounding_box()?
+            .ok_or_else(|| anyhow!("element is not visible and can't be clicked"))?;
+        let scale = self
+            .view
+            .evaluate_script("window.devicePixelRatio || 1")?
+            .as_number()
+            .unwrap_or(1.0);
+        let (x, y) = bounds.center();
+        let (x, y) = ((x * scale).round() as i32, (y * scale).round() as i32);
+
+        let view = &mut *self.view;
+        view.fire_mouse_event(&MouseEvent::new(
+            MouseEventType::kMouseEventType_MouseMoved,
+            x,
+            y,
+            MouseButton::kMouseButton_None,
+        ));
+        view.fire_mouse_event(&MouseEvent::new(
+            MouseEventType::kMouseEventType_MouseDown,
+            x,
+            y,
+            MouseButton::kMouseButton_Left,
+        ));
+        view.fire_mouse_event(&MouseEvent::new(
+            MouseEventType::kMouseEventType_MouseUp,
+            x,
+            y,
+            MouseButton::kMouseButton_Left,
+        ));
+        Ok(())
+    }
+
+    /// Give keyboard focus to the element.
+    pub fn focus(&mut self) -> Result<()> {
+        self.view.focus();
+        self.eval("el.focus();")?;
+        Ok(())
+    }
+
+    /// Type text into the element, appending to its current content.
+    ///
+    /// Each character is sent as a key down, a character and a key up event.
+    /// Characters without a key of their own (eg accented letters) use a key code of 0.
+    pub fn type_text(&mut self, text: &str) -> Result<()> {
+        self.focus()?;
+        for c in text.chars() {
+            for event in key_strokes(c).iter() {
+                self.view.fire_key_event(event);
+            }
+        }
+        Ok(())
+    }
+
+    /// Clear the element (an input, a textarea or an editable element) and type `text` into it.
+    pub fn fill(&mut self, text: &str) -> Result<()> {
+        self.eval(
+            "if ('value' in el) { el.value = ''; } \
+            else if (el.isContentEditable) { el.textContent = ''; } \
+            else { throw new Error('element is not editable'); }",
+        )?;
+        self.type_text(text)
+    }
+}
+
+impl Drop for ElementHandle<'_> {
+    fn drop(&mut self) {
+        // Fails if the page has been unloaded, the element is gone anyway
+        let _ = self.view.evaluate_script(&release_script(self.id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KeyEventType;
+
+    #[test]
+    fn scripts() {
+        let query = query_script("#login");
+        assert!(query.contains("document.querySelector(\"#login\")"));
+        assert!(query.contains("r.elements.set(r.next, el)"));
+        assert!(element_script(3, "return el.id;").contains(".elements.get(3)"));
+        assert_eq!(
+            release_script(3),
+            "window.__ultralight_rs_elements && window.__ultralight_rs_elements.elements.delete(3)"
+        );
+    }
+
+    #[test]
+    fn key_strokes_for_chars() {
+        let [down, char, up] = key_strokes('A');
+        assert_eq!(down.event_type, KeyEventType::kKeyEventType_RawKeyDown);
+        assert_eq!(char.event_type, KeyEventType::kKeyEventType_Char);
+        assert_eq!(up.event_type, KeyEventType::kKeyEventType_KeyUp);
+        assert_eq!(down.virtual_key_code, 'A' as i32);
+        assert_eq!(char.text, "A");
+        assert!([&down, &char, &up]
+            .iter()
+            .all(|e| e.modifiers == modifiers::SHIFT));
+
+        let [down, char, _] = key_strokes('a');
+        assert_eq!((down.virtual_key_code, down.modifiers), ('A' as i32, 0));
+        assert_eq!(char.text, "a");
+
+        let [down, char, _] = key_strokes('\n');
+        assert_eq!(down.virtual_key_code, key_codes::RETURN);
+        assert_eq!(char.text, "\r");
+
+        let [down, char, _] = key_strokes('é');
+        assert_eq!(down.virtual_key_code, 0);
+        assert_eq!(char.text, "é");
+    }
+}
@@ -1,13 +1,20 @@
-use std::ffi::{c_void, CString};
+use std::ffi::c_void;
+use std::path::Path;
+use std::slice;
+
+use anyhow::{bail, Result};
 
 use ultralight_sys::{
     ulBitmapErase, ulBitmapGetBpp, ulBitmapGetFormat, ulBitmapGetHeight, ulBitmapGetRowBytes,
     ulBitmapGetSize, ulBitmapGetWidth, ulBitmapIsEmpty, ulBitmapLockPixels, ulBitmapOwnsPixels,
-    ulBitmapSwapRedBlueChannels, ulBitmapUnlockPixels, ulBitmapWritePNG, ulCreateBitmap,
-    ulCreateBitmapFromCopy, ulCreateBitmapFromPixels, ulCreateEmptyBitmap, ulDestroyBitmap,
-    ULBitmap, ULBitmapFormat,
+    ulBitmapSwapRedBlueChannels, ulBitmapUnlockPixels, ulCreateBitmap, ulCreateBitmapFromCopy,
+    ulCreateBitmapFromPixels, ulCreateEmptyBitmap, ulDestroyBitmap, ULBitmap, ULBitmapFormat,
+    ULIntRect,
 };
 
+use crate::image::clamp_rect;
+use crate::{pixels, EncodeError, Image, ImageFormat, PixelFormat};
+
 pub struct Bitmap {
     pub raw: ULBitmap,
     created: bool,
@@ -102,7 +109,7 @@ impl Bitmap {
         unsafe { ulBitmapOwnsPixels(self.raw) }
     }
 
-    /// Lock pixels for reading/writing for the current scope.
+    /// Lock pixels for reading for the current scope.
     pub fn lock_pixels(&self) -> BitmapPixelsGuard {
         unsafe {
             BitmapPixelsGuard {
@@ -112,14 +119,45 @@ impl Bitmap {
         }
     }
 
-    /// Write bitmap to a PNG on disk.
-    pub fn write_to_png(&self, path: &str) -> bool {
+    /// Lock pixels for reading/writing for the current scope.
+    ///
+    /// Bitmaps created from the same raw handle with `From<ULBitmap>` share their pixels,
+    /// only one of them must be locked at a time while this guard is alive.
+    pub fn lock_pixels_mut(&mut self) -> BitmapPixelsGuardMut<'_> {
         unsafe {
-            let cstr = CString::new(path).unwrap();
-            ulBitmapWritePNG(self.raw, cstr.as_ptr())
+            BitmapPixelsGuardMut {
+                pixels: ulBitmapLockPixels(self.raw),
+                bitmap: self,
+            }
         }
     }
 
+    /// Write bitmap to a PNG on disk.
+    pub fn write_to_png<P: AsRef<Path>>(&self, path: P) -> Result<(), EncodeError> {
+        self.write_to(path, ImageFormat::Png)
+    }
+
+    /// Encode the bitmap to a file on disk.
+    pub fn write_to<P: AsRef<Path>>(
+        &self,
+        path: P,
+        format: ImageFormat,
+    ) -> Result<(), EncodeError> {
+        let data = self.encode(format)?;
+        std::fs::write(path, data)?;
+        Ok(())
+    }
+
+    /// Encode the bitmap in memory.
+    pub fn encode(&self, format: ImageFormat) -> Result<Vec<u8>, EncodeError> {
+        self.to_image().encode(format)
+    }
+
+    /// Encode the bitmap to PNG in memory.
+    pub fn encode_png(&self) -> Result<Vec<u8>, EncodeError> {
+        self.encode(ImageFormat::Png)
+    }
+
     /// Reset bitmap pixels to 0.
     pub fn erase(&mut self) {
         unsafe {
@@ -133,6 +171,205 @@ impl Bitmap {
             ulBitmapSwapRedBlueChannels(self.raw);
         }
     }
+
+    /// Create a BGRA bitmap from an image, converting it to premultiplied BGRA if needed.
+    pub fn from_image(image: &Image) -> Self {
+        let mut data = premultiplied_bgra(image);
+        Bitmap::new_from_pixels(
+            image.width,
+            image.height,
+            BitmapFormat::kBitmapFormat_BGRA8_UNORM_SRGB,
+            image.width * 4,
+            data.as_mut_ptr() as *mut c_void,
+            data.len() as u64,
+            true,
+        )
+    }
+
+    /// Copy the pixels to an owned premultiplied BGRA image.
+    /// A8 bitmaps are converted with [Bitmap::convert] first.
+    ///
+    /// Use [Image::to_format] and [Image::unpremultiplied] to get straight RGBA.
+    pub fn to_image(&self) -> Image {
+        if self.format() != BitmapFormat::kBitmapFormat_BGRA8_UNORM_SRGB {
+            return self
+                .convert(BitmapFormat::kBitmapFormat_BGRA8_UNORM_SRGB)
+                .to_image();
+        }
+        let guard = self.lock_pixels();
+        Image {
+            width: self.width(),
+            height: self.height(),
+            stride: self.width() * 4,
+            format: PixelFormat::Bgra8,
+            premultiplied: true,
+            data: guard.rows().flatten().copied().collect(),
+        }
+    }
+
+    /// Convert the bitmap to another pixel format.
+    ///
+    /// - A8 to BGRA8 : the alpha mask becomes white with that alpha, premultiplied.
+    /// - BGRA8 to A8 : only the alpha channel is kept.
+    pub fn convert(&self, format: BitmapFormat) -> Bitmap {
+        if self.format() == format {
+            return self.clone();
+        }
+        let mut converted = Bitmap::new(self.width(), self.height(), format);
+        {
+            let src = self.lock_pixels();
+            let mut dst = converted.lock_pixels_mut();
+            for (src_row, dst_row) in src.rows().zip(dst.rows_mut()) {
+                convert_row(src_row, dst_row, format);
+            }
+        }
+        converted
+    }
+
+    /// Copy a region of the bitmap to a new bitmap with the same format.
+    /// The region is clamped to the bitmap bounds.
+    pub fn crop(&self, rect: ULIntRect) -> Bitmap {
+        let rect = clamp_rect(rect, self.width(), self.height());
+        let mut cropped = Bitmap::new(
+            (rect.right - rect.left) as u32,
+            (rect.bottom - rect.top) as u32,
+            self.format(),
+        );
+        // Formats match, this can't fail
+        cropped.blit(self, rect, 0, 0).unwrap();
+        cropped
+    }
+
+    /// Copy the `src_rect` region of `src` to this bitmap at (`dst_x`, `dst_y`), without blending.
+    /// The copied area is clipped to both bitmaps bounds. Both bitmaps must have the same format.
+    pub fn blit(
+        &mut self,
+        src: &Bitmap,
+        src_rect: ULIntRect,
+        dst_x: i32,
+        dst_y: i32,
+    ) -> Result<()> {
+        if src.format() != self.format() {
+            bail!(
+                "can't blit a {:?} bitmap onto a {:?} bitmap",
+                src.format(),
+                self.format()
+            );
+        }
+        if src.raw == self.raw {
+            // Both wrap the same bitmap, don't lock it twice
+            return self.blit(&src.clone(), src_rect, dst_x, dst_y);
+        }
+        let (src_rect, dst_x, dst_y) = match clip_blit(
+            (src.width(), src.height()),
+            src_rect,
+            (self.width(), self.height()),
+            dst_x,
+            dst_y,
+        ) {
+            Some(clipped) => clipped,
+            None => return Ok(()),
+        };
+
+        let bpp = self.bpp() as usize;
+        let src_row_bytes = src.row_bytes() as usize;
+        let dst_row_bytes = self.row_bytes() as usize;
+        let src_pixels = src.lock_pixels();
+        let mut dst_pixels = self.lock_pixels_mut();
+        copy_rect(
+            src_pixels.as_slice(),
+            src_row_bytes,
+            src_rect,
+            dst_pixels.as_mut_slice(),
+            dst_row_bytes,
+            (dst_x, dst_y),
+            bpp,
+        );
+        Ok(())
+    }
+}
+
+/// Tightly packed premultiplied BGRA pixels of an image.
+fn premultiplied_bgra(image: &Image) -> Vec<u8> {
+    let mut data: Vec<u8> = image.rows().flatten().copied().collect();
+    if image.format == PixelFormat::Rgba8 {
+        pixels::swap_red_blue(&mut data);
+    }
+    if !image.premultiplied {
+        pixels::premultiply(&mut data);
+    }
+    data
+}
+
+/// Convert a row of pixels to `format`, from the other format.
+///
+/// - A8 to BGRA8 : the alpha mask becomes white with that alpha, premultiplied.
+/// - BGRA8 to A8 : only the alpha channel is kept.
+fn convert_row(src_row: &[u8], dst_row: &mut [u8], format: BitmapFormat) {
+    match format {
+        BitmapFormat::kBitmapFormat_BGRA8_UNORM_SRGB => {
+            for (a, pixel) in src_row.iter().zip(dst_row.chunks_exact_mut(4)) {
+                pixel.copy_from_slice(&[*a, *a, *a, *a]);
+            }
+        }
+        BitmapFormat::kBitmapFormat_A8_UNORM => {
+            for (pixel, a) in src_row.chunks_exact(4).zip(dst_row.iter_mut()) {
+                *a = pixel[3];
+            }
+        }
+    }
+}
+
+/// Clip a blit of `src_rect` at (`dst_x`, `dst_y`) to the source and destination sizes.
+/// Returns the source region and the destination position, `None` if nothing is copied.
+fn clip_blit(
+    src_size: (u32, u32),
+    src_rect: ULIntRect,
+    dst_size: (u32, u32),
+    dst_x: i32,
+    dst_y: i32,
+) -> Option<(ULIntRect, usize, usize)> {
+    let mut src_rect = clamp_rect(src_rect, src_size.0, src_size.1);
+    // Clip against the left and top edges of the destination
+    let (mut dst_x, mut dst_y) = (dst_x, dst_y);
+    if dst_x < 0 {
+        src_rect.left -= dst_x;
+        dst_x = 0;
+    }
+    if dst_y < 0 {
+        src_rect.top -= dst_y;
+        dst_y = 0;
+    }
+    // Then against the right and bottom edges
+    src_rect.right = src_rect
+        .right
+        .min(src_rect.left + (dst_size.0 as i32 - dst_x));
+    src_rect.bottom = src_rect
+        .bottom
+        .min(src_rect.top + (dst_size.1 as i32 - dst_y));
+    if src_rect.right <= src_rect.left || src_rect.bottom <= src_rect.top {
+        return None;
+    }
+    Some((src_rect, dst_x as usize, dst_y as usize))
+}
+
+/// Copy the `rect` region of a strided buffer to another one at `dst_pos`.
+/// The region must fit in both buffers.
+fn copy_rect(
+    src: &[u8],
+    src_stride: usize,
+    rect: ULIntRect,
+    dst: &mut [u8],
+    dst_stride: usize,
+    dst_pos: (usize, usize),
+    bpp: usize,
+) {
+    let len = (rect.right - rect.left) as usize * bpp;
+    for y in 0..(rect.bottom - rect.top) as usize {
+        let src_start = (rect.top as usize + y) * src_stride + rect.left as usize * bpp;
+        let dst_start = (dst_pos.1 + y) * dst_stride + dst_pos.0 * bpp;
+        dst[dst_start..dst_start + len].copy_from_slice(&src[src_start..src_start + len]);
+    }
 }
 
 impl Clone for Bitmap {
@@ -165,13 +402,166 @@ impl Drop for Bitmap {
     }
 }
 
+/// Iterate over the rows of a pixel buffer, without the padding.
+fn rows(data: &[u8], row_bytes: usize, row_len: usize) -> impl Iterator<Item = &[u8]> {
+    data.chunks(row_bytes.max(1))
+        .map(move |row| &row[..row_len])
+}
+
+/// Number of bytes between rows and number of bytes of pixels in a row.
+fn row_layout(bitmap: &Bitmap) -> (usize, usize) {
+    let row_len = (bitmap.width() * bitmap.bpp()) as usize;
+    (bitmap.row_bytes() as usize, row_len)
+}
+
+/// Read access to the pixels of a [Bitmap], see [Bitmap::lock_pixels].
 pub struct BitmapPixelsGuard<'a> {
     pub pixels: *mut c_void,
     bitmap: &'a Bitmap,
 }
 
+impl BitmapPixelsGuard<'_> {
+    /// The whole pixel buffer, including the padding at the end of each row.
+    pub fn as_slice(&self) -> &[u8] {
+        if self.pixels.is_null() {
+            return &[];
+        }
+        unsafe { slice::from_raw_parts(self.pixels as *const u8, self.bitmap.size() as usize) }
+    }
+
+    /// Iterate over the rows of pixels, without the padding.
+    pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        let (row_bytes, row_len) = row_layout(self.bitmap);
+        rows(self.as_slice(), row_bytes, row_len)
+    }
+}
+
 impl Drop for BitmapPixelsGuard<'_> {
     fn drop(&mut self) {
         unsafe { ulBitmapUnlockPixels(self.bitmap.raw) }
     }
 }
+
+/// Read/write access to the pixels of a [Bitmap], see [Bitmap::lock_pixels_mut].
+pub struct BitmapPixelsGuardMut<'a> {
+    pub pixels: *mut c_void,
+    bitmap: &'a mut Bitmap,
+}
+
+impl BitmapPixelsGuardMut<'_> {
+    /// The whole pixel buffer, including the padding at the end of each row.
+    pub fn as_slice(&self) -> &[u8] {
+        if self.pixels.is_null() {
+            return &[];
+        }
+        unsafe { slice::from_raw_parts(self.pixels as *const u8, self.bitmap.size() as usize) }
+    }
+
+    /// The whole pixel buffer, including the padding at the end of each row.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        if self.pixels.is_null() {
+            return &mut [];
+        }
+        unsafe { slice::from_raw_parts_mut(self.pixels as *mut u8, self.bitmap.size() as usize) }
+    }
+
+    /// Iterate over the rows of pixels, without the padding.
+    pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        let (row_bytes, row_len) = row_layout(self.bitmap);
+        rows(self.as_slice(), row_bytes, row_len)
+    }
+
+    /// Iterate over the rows of pixels, without the padding.
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [u8]> {
+        let (row_bytes, row_len) = row_layout(self.bitmap);
+        self.as_mut_slice()
+            .chunks_mut(row_bytes.max(1))
+            .map(move |row| &mut row[..row_len])
+    }
+}
+
+impl Drop for BitmapPixelsGuardMut<'_> {
+    fn drop(&mut self) {
+        unsafe { ulBitmapUnlockPixels(self.bitmap.raw) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(left: i32, top: i32, right: i32, bottom: i32) -> ULIntRect {
+        ULIntRect {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    #[test]
+    fn image_to_bitmap_pixels() {
+        let image = Image {
+            width: 1,
+            height: 2,
+            stride: 8,
+            format: PixelFormat::Rgba8,
+            premultiplied: false,
+            data: vec![200, 100, 50, 128, 0, 0, 0, 0, 10, 20, 30, 255, 0, 0, 0, 0],
+        };
+        assert_eq!(
+            premultiplied_bgra(&image),
+            vec![25, 50, 100, 128, 30, 20, 10, 255]
+        );
+    }
+
+    #[test]
+    fn bitmap_rows() {
+        let data = [1, 2, 0, 3, 4, 0];
+        let packed: Vec<u8> = rows(&data, 3, 2).flatten().copied().collect();
+        assert_eq!(packed, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn convert_rows() {
+        let mut bgra = [0; 8];
+        convert_row(
+            &[0, 128],
+            &mut bgra,
+            BitmapFormat::kBitmapFormat_BGRA8_UNORM_SRGB,
+        );
+        assert_eq!(bgra, [0, 0, 0, 0, 128, 128, 128, 128]);
+        let mut a8 = [0; 2];
+        convert_row(
+            &[1, 2, 3, 4, 5, 6, 7, 8],
+            &mut a8,
+            BitmapFormat::kBitmapFormat_A8_UNORM,
+        );
+        assert_eq!(a8, [4, 8]);
+    }
+
+    #[test]
+    fn clip_blits() {
+        // Fully inside
+        let (r, x, y) = clip_blit((4, 4), rect(1, 1, 3, 3), (4, 4), 2, 2).unwrap();
+        assert_eq!((r.left, r.top, r.right, r.bottom, x, y), (1, 1, 3, 3, 2, 2));
+        // Clipped by the destination edges
+        let (r, x, y) = clip_blit((4, 4), rect(0, 0, 4, 4), (3, 3), -1, 2).unwrap();
+        assert_eq!((r.left, r.top, r.right, r.bottom, x, y), (1, 0, 4, 1, 0, 2));
+        // Crop : clamped to the source
+        let (r, ..) = clip_blit((4, 4), rect(-5, 2, 10, 10), (4, 2), 0, 0).unwrap();
+        assert_eq!((r.left, r.top, r.right, r.bottom), (0, 2, 4, 4));
+        // Nothing left
+        assert!(clip_blit((4, 4), rect(0, 0, 4, 4), (4, 4), 4, 0).is_none());
+        assert!(clip_blit((4, 4), rect(0, 0, 2, 2), (4, 4), -2, 0).is_none());
+    }
+
+    #[test]
+    fn copy_rects() {
+        // 3x2 A8 source with a padding byte per row, into a 2x2 destination
+        let src = [1, 2, 3, 0, 4, 5, 6, 0];
+        let mut dst = [0; 4];
+        copy_rect(&src, 4, rect(1, 0, 3, 1), &mut dst, 2, (0, 1), 1);
+        assert_eq!(dst, [0, 0, 2, 3]);
+    }
+}
@@ -37,7 +37,7 @@ impl Config {
     pub fn resource_path(&self, resource_path: &str) {
         unsafe {
             let ulstr: ULString = resource_path.into();
-            ulConfigSetResourcePath(self.raw, ulstr.into());
+            ulConfigSetResourcePath(self.raw, ulstr.raw());
         }
     }
 
@@ -46,7 +46,7 @@ impl Config {
     pub fn cache_path(&self, cache_path: &str) {
         unsafe {
             let ulstr: ULString = cache_path.into();
-            ulConfigSetCachePath(self.raw, ulstr.into());
+            ulConfigSetCachePath(self.raw, ulstr.raw());
         }
     }
 
@@ -115,7 +115,7 @@ impl Config {
     pub fn font_family_standard(&self, font_family_standard: &str) {
         unsafe {
             let ulstr: ULString = font_family_standard.into();
-            ulConfigSetFontFamilyStandard(self.raw, ulstr.into());
+            ulConfigSetFontFamilyStandard(self.raw, ulstr.raw());
         }
     }
 
@@ -123,7 +123,7 @@ impl Config {
     pub fn font_family_fixed(&self, font_family_fixed: &str) {
         unsafe {
             let ulstr: ULString = font_family_fixed.into();
-            ulConfigSetFontFamilyFixed(self.raw, ulstr.into());
+            ulConfigSetFontFamilyFixed(self.raw, ulstr.raw());
         }
     }
 
@@ -131,7 +131,7 @@ impl Config {
     pub fn font_family_serif(&self, font_family_serif: &str) {
         unsafe {
             let ulstr: ULString = font_family_serif.into();
-            ulConfigSetFontFamilySerif(self.raw, ulstr.into());
+            ulConfigSetFontFamilySerif(self.raw, ulstr.raw());
         }
     }
 
@@ -139,7 +139,7 @@ impl Config {
     pub fn font_family_sans_serif(&self, font_family_sans_serif: &str) {
         unsafe {
             let ulstr: ULString = font_family_sans_serif.into();
-            ulConfigSetFontFamilySansSerif(self.raw, ulstr.into());
+            ulConfigSetFontFamilySansSerif(self.raw, ulstr.raw());
         }
     }
 
@@ -147,7 +147,7 @@ impl Config {
     pub fn user_agent(&self, user_agent: &str) {
         unsafe {
             let ulstr: ULString = user_agent.into();
-            ulConfigSetUserAgent(self.raw, ulstr.into());
+            ulConfigSetUserAgent(self.raw, ulstr.raw());
         }
     }
 
@@ -157,7 +157,7 @@ impl Config {
     pub fn user_stylesheet(&self, user_stylesheet: &str) {
         unsafe {
             let ulstr: ULString = user_stylesheet.into();
-            ulConfigSetUserStylesheet(self.raw, ulstr.into());
+            ulConfigSetUserStylesheet(self.raw, ulstr.raw());
         }
     }
 
@@ -0,0 +1,170 @@
+//! PNG decoding.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use png::{ColorType, Decoder, DecodingError, Limits, Transformations};
+
+use crate::{Image, PixelFormat};
+
+/// Largest decoded image accepted by [Image::decode_png], in bytes of RGBA pixels (1 GiB).
+pub const MAX_DECODED_SIZE: usize = 1 << 30;
+
+/// Error returned when reading or decoding an image fails.
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The data is corrupted or isn't in the expected format.
+    Invalid(String),
+    /// The data uses a feature this decoder doesn't support.
+    Unsupported(String),
+    /// The decoded image would be larger than [MAX_DECODED_SIZE].
+    TooLarge,
+    /// Reading the file failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Invalid(reason) => write!(f, "invalid data : {}", reason),
+            DecodeError::Unsupported(feature) => write!(f, "unsupported feature : {}", feature),
+            DecodeError::TooLarge => write!(f, "image too large"),
+            DecodeError::Io(e) => write!(f, "failed to read image : {}", e),
+        }
+    }
+}
+
+impl Error for DecodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DecodeError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DecodeError {
+    fn from(e: io::Error) -> Self {
+        DecodeError::Io(e)
+    }
+}
+
+impl From<DecodingError> for DecodeError {
+    fn from(e: DecodingError) -> Self {
+        match e {
+            DecodingError::IoError(e) => DecodeError::Io(e),
+            DecodingError::Format(e) => DecodeError::Invalid(e.to_string()),
+            DecodingError::Parameter(e) => DecodeError::Invalid(e.to_string()),
+            DecodingError::LimitsExceeded => DecodeError::TooLarge,
+        }
+    }
+}
+
+impl Image {
+    /// Decode a PNG file to a straight alpha RGBA image.
+    pub fn open_png<P: AsRef<Path>>(path: P) -> Result<Image, DecodeError> {
+        Image::decode_png(&std::fs::read(path)?)
+    }
+
+    /// Decode a PNG image in memory to a straight alpha RGBA image.
+    /// Images larger than [MAX_DECODED_SIZE] once decoded are rejected before decompressing them.
+    pub fn decode_png(data: &[u8]) -> Result<Image, DecodeError> {
+        decode_png(data)
+    }
+}
+
+fn decode_png(data: &[u8]) -> Result<Image, DecodeError> {
+    let mut decoder = Decoder::new_with_limits(
+        data,
+        Limits {
+            bytes: MAX_DECODED_SIZE,
+        },
+    );
+    // Palettes, transparency keys and low bit depths are expanded, 16 bits samples are stripped
+    decoder.set_transformations(Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let (width, height) = (reader.info().width, reader.info().height);
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .filter(|&size| size <= MAX_DECODED_SIZE)
+        .ok_or(DecodeError::TooLarge)?;
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buffer)?;
+    buffer.truncate(frame.buffer_size());
+    let data = match frame.color_type {
+        ColorType::Rgba => buffer,
+        ColorType::Rgb => buffer
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        ColorType::Grayscale => buffer.iter().flat_map(|&v| [v, v, v, 255]).collect(),
+        ColorType::Indexed => {
+            return Err(DecodeError::Unsupported(
+                "indexed colors without palette expansion".to_string(),
+            ))
+        }
+    };
+
+    Ok(Image {
+        width,
+        height,
+        stride: width * 4,
+        format: PixelFormat::Rgba8,
+        premultiplied: false,
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ImageFormat;
+
+    #[test]
+    fn png_roundtrip() {
+        let image = Image {
+            width: 5,
+            height: 3,
+            stride: 20,
+            format: PixelFormat::Rgba8,
+            premultiplied: false,
+            data: (0..60).map(|i| (i * 37 % 256) as u8).collect(),
+        };
+        let png = image.encode(ImageFormat::Png).unwrap();
+        assert_eq!(Image::decode_png(&png).unwrap(), image);
+    }
+
+    #[test]
+    fn oversized_png() {
+        let image = Image {
+            width: 1,
+            height: 1,
+            stride: 4,
+            format: PixelFormat::Rgba8,
+            premultiplied: false,
+            data: vec![0; 4],
+        };
+        let mut png = image.encode(ImageFormat::Png).unwrap();
+        // 65536x65536 pixels in IHDR, with its CRC-32 fixed
+        png[16..24].copy_from_slice(&[0, 1, 0, 0, 0, 1, 0, 0]);
+        let mut crc = flate2::Crc::new();
+        crc.update(&png[12..29]);
+        png[29..33].copy_from_slice(&crc.sum().to_be_bytes());
+        assert!(matches!(
+            Image::decode_png(&png),
+            Err(DecodeError::TooLarge)
+        ));
+        assert!(matches!(
+            Image::decode_png(b"GIF89a"),
+            Err(DecodeError::Invalid(_))
+        ));
+    }
+}
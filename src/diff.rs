@@ -0,0 +1,363 @@
+//! Rendered pages are compared to golden PNG images, pixel by pixel.
+//!
+//! ```no_run
+//! # use ultralight_rs::assert_view_matches;
+//! # fn test(view: &ultralight_rs::View) {
+//! // Relative paths are resolved against the crate manifest directory
+//! assert_view_matches!(view, "tests/goldens/home.png");
+//! # }
+//! ```
+//!
+//! Set the `ULTRALIGHT_UPDATE_GOLDENS` environment variable to write the current rendering
+//! to the golden files instead of comparing.
+
+use std::path::{Path, PathBuf};
+
+use crate::{Bitmap, DecodeError, Image, ImageFormat, PixelFormat, View};
+
+/// Environment variable making [assert_view_matches!](crate::assert_view_matches) update goldens.
+pub const UPDATE_GOLDENS_ENV: &str = "ULTRALIGHT_UPDATE_GOLDENS";
+
+/// How strict the comparison is.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DiffOptions {
+    /// Maximum difference per channel (0-255) for pixels to be considered equal.
+    pub tolerance: u8,
+    /// Ignore pixels detected as anti-aliasing, which tend to vary between machines.
+    pub anti_aliasing: bool,
+    /// Number of different pixels allowed for the images to match.
+    pub max_diff_pixels: usize,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions {
+            tolerance: 2,
+            anti_aliasing: true,
+            max_diff_pixels: 0,
+        }
+    }
+}
+
+/// Outcome of a comparison.
+#[derive(Debug, Clone)]
+pub struct DiffResult {
+    /// Size of the actual and expected images.
+    pub actual_size: (u32, u32),
+    pub expected_size: (u32, u32),
+    /// Number of pixels above the tolerance.
+    pub diff_pixels: usize,
+    /// Number of different pixels ignored as anti-aliasing.
+    pub anti_aliased_pixels: usize,
+    /// The expected image faded to gray, with different pixels in red and anti-aliasing in yellow.
+    /// Empty when the sizes differ.
+    pub diff_image: Image,
+    max_diff_pixels: usize,
+}
+
+impl DiffResult {
+    /// Whether or not the images are considered identical.
+    pub fn is_match(&self) -> bool {
+        self.actual_size == self.expected_size && self.diff_pixels <= self.max_diff_pixels
+    }
+}
+
+/// Straight RGBA pixels, tightly packed.
+struct Pixels {
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+}
+
+impl Pixels {
+    fn new(image: &Image) -> Self {
+        let image = image
+            .clone()
+            .to_format(PixelFormat::Rgba8)
+            .unpremultiplied();
+        Pixels {
+            width: image.width as usize,
+            height: image.height as usize,
+            data: image.rows().flatten().copied().collect(),
+        }
+    }
+
+    fn get(&self, x: usize, y: usize) -> &[u8] {
+        let i = (y * self.width + x) * 4;
+        &self.data[i..i + 4]
+    }
+
+    /// Luma of the pixel blended over white.
+    fn luma(&self, x: usize, y: usize) -> f32 {
+        let p = self.get(x, y);
+        let alpha = p[3] as f32 / 255.0;
+        let blend = |c: u8| 255.0 + (c as f32 - 255.0) * alpha;
+        blend(p[0]) * 0.299 + blend(p[1]) * 0.587 + blend(p[2]) * 0.114
+    }
+
+    /// 3x3 neighbourhood of a pixel, clipped to the image.
+    fn neighbours(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        let (x0, x1) = (x.saturating_sub(1), (x + 1).min(self.width - 1));
+        let (y0, y1) = (y.saturating_sub(1), (y + 1).min(self.height - 1));
+        (y0..=y1)
+            .flat_map(move |ny| (x0..=x1).map(move |nx| (nx, ny)))
+            .filter(move |(nx, ny)| (*nx, *ny) != (x, y))
+    }
+
+    fn on_edge(&self, x: usize, y: usize) -> bool {
+        x == 0 || y == 0 || x == self.width - 1 || y == self.height - 1
+    }
+
+    /// Whether or not more than 2 neighbours have exactly the same color.
+    fn has_many_siblings(&self, x: usize, y: usize) -> bool {
+        let mut same = if self.on_edge(x, y) { 1 } else { 0 };
+        let color = self.get(x, y);
+        for (nx, ny) in self.neighbours(x, y) {
+            if self.get(nx, ny) == color {
+                same += 1;
+                if same > 2 {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Detect anti-aliasing like pixelmatch : the pixel is between its darkest and brightest
+    /// neighbours, and one of them belongs to a flat area in both images.
+    fn is_anti_aliased(&self, x: usize, y: usize, other: &Pixels) -> bool {
+        let center = self.luma(x, y);
+        let mut same = if self.on_edge(x, y) { 1 } else { 0 };
+        let (mut min, mut max) = (0.0f32, 0.0f32);
+        let (mut darkest, mut brightest) = (None, None);
+        for (nx, ny) in self.neighbours(x, y) {
+            let delta = self.luma(nx, ny) - center;
+            if delta == 0.0 {
+                same += 1;
+                if same > 2 {
+                    return false;
+                }
+            } else if delta < min {
+                min = delta;
+                darkest = Some((nx, ny));
+            } else if delta > max {
+                max = delta;
+                brightest = Some((nx, ny));
+            }
+        }
+        let flat = |pos: Option<(usize, usize)>| {
+            pos.is_some_and(|(px, py)| {
+                self.has_many_siblings(px, py) && other.has_many_siblings(px, py)
+            })
+        };
+        darkest.is_some() && brightest.is_some() && (flat(darkest) || flat(brightest))
+    }
+}
+
+/// Compare two images, images of different sizes never match.
+pub fn diff_images(actual: &Image, expected: &Image, options: &DiffOptions) -> DiffResult {
+    let mut result = DiffResult {
+        actual_size: (actual.width, actual.height),
+        expected_size: (expected.width, expected.height),
+        diff_pixels: 0,
+        anti_aliased_pixels: 0,
+        diff_image: Image {
+            width: 0,
+            height: 0,
+            stride: 0,
+            format: PixelFormat::Rgba8,
+            premultiplied: false,
+            data: Vec::new(),
+        },
+        max_diff_pixels: options.max_diff_pixels,
+    };
+    if result.actual_size != result.expected_size || actual.width == 0 || actual.height == 0 {
+        return result;
+    }
+
+    let (a, b) = (Pixels::new(actual), Pixels::new(expected));
+    let mut diff = Vec::with_capacity(a.data.len());
+    for y in 0..a.height {
+        for x in 0..a.width {
+            let (pa, pb) = (a.get(x, y), b.get(x, y));
+            let different = pa.iter().zip(pb).any(|(ca, cb)| {
+                (*ca as i16 - *cb as i16).unsigned_abs() > options.tolerance as u16
+            });
+            let pixel = if !different {
+                // Faded to make the changes stand out
+                let gray = (255.0 - (255.0 - b.luma(x, y)) * 0.1) as u8;
+                [gray, gray, gray, 255]
+            } else if options.anti_aliasing
+                && (a.is_anti_aliased(x, y, &b) || b.is_anti_aliased(x, y, &a))
+            {
+                result.anti_aliased_pixels += 1;
+                [255, 255, 0, 255]
+            } else {
+                result.diff_pixels += 1;
+                [255, 0, 0, 255]
+            };
+            diff.extend_from_slice(&pixel);
+        }
+    }
+    result.diff_image = Image {
+        width: actual.width,
+        height: actual.height,
+        stride: actual.width * 4,
+        format: PixelFormat::Rgba8,
+        premultiplied: false,
+        data: diff,
+    };
+    result
+}
+
+/// Compare a bitmap to a reference PNG file.
+pub fn compare_bitmap<P: AsRef<Path>>(
+    bitmap: &Bitmap,
+    reference: P,
+    options: &DiffOptions,
+) -> Result<DiffResult, DecodeError> {
+    let expected = Image::open_png(reference)?;
+    Ok(diff_images(&bitmap.to_image(), &expected, options))
+}
+
+/// Implementation of [assert_view_matches!](crate::assert_view_matches).
+#[doc(hidden)]
+pub fn assert_view_matches(view: &View, manifest_dir: &str, golden: &str, options: &DiffOptions) {
+    let path = Path::new(manifest_dir).join(golden);
+    let actual = view
+        .screenshot()
+        .expect("the view has no surface, the GPU renderer can't be compared");
+
+    if std::env::var_os(UPDATE_GOLDENS_ENV).is_some() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        std::fs::write(&path, actual.encode(ImageFormat::Png).unwrap())
+            .unwrap_or_else(|e| panic!("couldn't update golden {} : {}", path.display(), e));
+        return;
+    }
+
+    let expected = Image::open_png(&path).unwrap_or_else(|e| {
+        panic!(
+            "couldn't read golden {} : {}\nrun with {}=1 to create it",
+            path.display(),
+            e,
+            UPDATE_GOLDENS_ENV
+        )
+    });
+    let result = diff_images(&actual, &expected, options);
+    if result.is_match() {
+        return;
+    }
+
+    let sibling = |suffix: &str| -> PathBuf {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        path.with_file_name(format!("{}.{}.png", stem, suffix))
+    };
+    let actual_path = sibling("actual");
+    let _ = std::fs::write(&actual_path, actual.encode(ImageFormat::Png).unwrap());
+    let mut message = if result.actual_size != result.expected_size {
+        format!(
+            "view size {:?} doesn't match golden size {:?}",
+            result.actual_size, result.expected_size
+        )
+    } else {
+        let diff_path = sibling("diff");
+        let _ = std::fs::write(
+            &diff_path,
+            result.diff_image.encode(ImageFormat::Png).unwrap(),
+        );
+        format!(
+            "{} pixels differ ({} allowed), diff written to {}",
+            result.diff_pixels,
+            options.max_diff_pixels,
+            diff_path.display()
+        )
+    };
+    message += &format!(
+        "\nactual rendering written to {}\nrun with {}=1 to update the golden",
+        actual_path.display(),
+        UPDATE_GOLDENS_ENV
+    );
+    panic!("view doesn't match golden {} : {}", path.display(), message);
+}
+
+/// Assert that a [View](crate::View) renders like a golden PNG image.
+///
+/// The path is relative to the manifest directory of the calling crate.
+/// On mismatch, `<name>.actual.png` and `<name>.diff.png` are written next to the golden.
+/// When the `ULTRALIGHT_UPDATE_GOLDENS` environment variable is set, the golden is overwritten instead.
+///
+/// An optional third argument sets the [DiffOptions](crate::diff::DiffOptions).
+#[macro_export]
+macro_rules! assert_view_matches {
+    ($view:expr, $golden:expr) => {
+        $crate::assert_view_matches!($view, $golden, &$crate::diff::DiffOptions::default())
+    };
+    ($view:expr, $golden:expr, $options:expr) => {
+        $crate::diff::assert_view_matches(&$view, env!("CARGO_MANIFEST_DIR"), $golden, $options)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, height: u32, data: Vec<u8>) -> Image {
+        Image {
+            width,
+            height,
+            stride: width * 4,
+            format: PixelFormat::Rgba8,
+            premultiplied: false,
+            data,
+        }
+    }
+
+    #[test]
+    fn tolerance() {
+        let a = image(2, 1, vec![10, 10, 10, 255, 200, 200, 200, 255]);
+        let b = image(2, 1, vec![12, 10, 10, 255, 200, 200, 200, 255]);
+        let options = DiffOptions::default();
+        assert!(diff_images(&a, &b, &options).is_match());
+
+        let strict = DiffOptions {
+            tolerance: 0,
+            ..options
+        };
+        let result = diff_images(&a, &b, &strict);
+        assert_eq!(result.diff_pixels, 1);
+        assert_eq!(result.diff_image.pixel(0, 0), [255, 0, 0, 255]);
+        assert!(!result.is_match());
+    }
+
+    #[test]
+    fn size_mismatch() {
+        let a = image(1, 1, vec![0; 4]);
+        let b = image(2, 1, vec![0; 8]);
+        let result = diff_images(&a, &b, &DiffOptions::default());
+        assert!(!result.is_match());
+        assert_eq!(result.diff_image.rows().count(), 0);
+    }
+
+    #[test]
+    fn anti_aliased_edge() {
+        // A black/white vertical edge, one side shifted by a gray anti-aliased column
+        let row = |middle: u8| {
+            [0u8, 0, middle, 255, 255]
+                .iter()
+                .flat_map(|v| vec![*v, *v, *v, 255])
+                .collect::<Vec<u8>>()
+        };
+        let a = image(5, 3, [row(128), row(128), row(128)].concat());
+        let b = image(5, 3, [row(255), row(255), row(255)].concat());
+
+        let result = diff_images(&a, &b, &DiffOptions::default());
+        assert_eq!((result.diff_pixels, result.anti_aliased_pixels), (0, 3));
+        let exact = DiffOptions {
+            anti_aliasing: false,
+            ..DiffOptions::default()
+        };
+        assert_eq!(diff_images(&a, &b, &exact).diff_pixels, 3);
+    }
+}
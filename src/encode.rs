@@ -0,0 +1,257 @@
+//! Image encoders, so images can be encoded in memory.
+//!
+//! Supported formats are PNG, BMP, PPM and QOI.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use png::{BitDepth, ColorType, Encoder};
+
+use crate::{Image, PixelFormat};
+
+/// File format for [Image::encode].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// Lossless, compressed, with alpha.
+    Png,
+    /// Uncompressed 32-bit BGRA, bottom-up.
+    Bmp,
+    /// Binary portable pixmap (P6), alpha is discarded (composited over black).
+    Ppm,
+    /// The Quite OK Image format, lossless, with alpha.
+    Qoi,
+}
+
+impl ImageFormat {
+    /// Usual file extension for the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Bmp => "bmp",
+            ImageFormat::Ppm => "ppm",
+            ImageFormat::Qoi => "qoi",
+        }
+    }
+}
+
+/// Error returned when encoding or writing an image fails.
+#[derive(Debug)]
+pub enum EncodeError {
+    /// The image has no pixels.
+    Empty,
+    /// The image dimensions can't be represented in the format.
+    TooLarge { width: u32, height: u32 },
+    /// The pixel buffer is smaller than what the dimensions require.
+    InvalidBuffer,
+    /// Writing the encoded image failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::Empty => write!(f, "can't encode an empty image"),
+            EncodeError::TooLarge { width, height } => {
+                write!(f, "image is too large to be encoded ({}x{})", width, height)
+            }
+            EncodeError::InvalidBuffer => write!(f, "pixel buffer is too small for the image"),
+            EncodeError::Io(e) => write!(f, "failed to write image : {}", e),
+        }
+    }
+}
+
+impl Error for EncodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EncodeError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for EncodeError {
+    fn from(e: io::Error) -> Self {
+        EncodeError::Io(e)
+    }
+}
+
+impl Image {
+    /// Encode the image in memory.
+    pub fn encode(&self, format: ImageFormat) -> Result<Vec<u8>, EncodeError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(EncodeError::Empty);
+        }
+        let required = (self.height as usize - 1) * self.stride as usize + self.width as usize * 4;
+        if self.stride < self.width * 4 || self.data.len() < required {
+            return Err(EncodeError::InvalidBuffer);
+        }
+        match format {
+            ImageFormat::Png => png(self),
+            ImageFormat::Bmp => bmp(self),
+            ImageFormat::Ppm => Ok(ppm(self)),
+            ImageFormat::Qoi => qoi(self),
+        }
+    }
+}
+
+/// Rows of straight RGBA pixels.
+fn straight_rgba_rows(image: &Image) -> Vec<u8> {
+    let mut rgba = image
+        .clone()
+        .to_format(PixelFormat::Rgba8)
+        .unpremultiplied();
+    if rgba.stride != rgba.width * 4 {
+        rgba.data = rgba.rows().flatten().copied().collect();
+    }
+    rgba.data
+}
+
+fn png(image: &Image) -> Result<Vec<u8>, EncodeError> {
+    let mut out = Vec::new();
+    let mut encoder = Encoder::new(&mut out, image.width, image.height);
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_depth(BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(io::Error::from)?;
+    writer
+        .write_image_data(&straight_rgba_rows(image))
+        .map_err(io::Error::from)?;
+    writer.finish().map_err(io::Error::from)?;
+    Ok(out)
+}
+
+fn bmp(image: &Image) -> Result<Vec<u8>, EncodeError> {
+    if image.width > i32::MAX as u32 || image.height > i32::MAX as u32 {
+        return Err(EncodeError::TooLarge {
+            width: image.width,
+            height: image.height,
+        });
+    }
+    let bgra = image
+        .clone()
+        .to_format(PixelFormat::Bgra8)
+        .unpremultiplied();
+    let pixels_size = image.width as u64 * image.height as u64 * 4;
+    if pixels_size + 54 > u32::MAX as u64 {
+        return Err(EncodeError::TooLarge {
+            width: image.width,
+            height: image.height,
+        });
+    }
+
+    let mut out = Vec::with_capacity(54 + pixels_size as usize);
+    // BITMAPFILEHEADER
+    out.extend_from_slice(b"BM");
+    out.extend_from_slice(&(54 + pixels_size as u32).to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&54u32.to_le_bytes());
+    // BITMAPINFOHEADER
+    out.extend_from_slice(&40u32.to_le_bytes());
+    out.extend_from_slice(&(image.width as i32).to_le_bytes());
+    out.extend_from_slice(&(image.height as i32).to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&32u16.to_le_bytes());
+    // BI_RGB
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(&(pixels_size as u32).to_le_bytes());
+    // 72 DPI
+    out.extend_from_slice(&2835i32.to_le_bytes());
+    out.extend_from_slice(&2835i32.to_le_bytes());
+    out.extend_from_slice(&[0; 8]);
+
+    // Rows are 4 bytes aligned already, stored bottom-up
+    let rows: Vec<&[u8]> = bgra.rows().collect();
+    for row in rows.iter().rev() {
+        out.extend_from_slice(row);
+    }
+    Ok(out)
+}
+
+fn ppm(image: &Image) -> Vec<u8> {
+    // Premultiplied values are the pixels composited over black
+    let rgba = image.clone().to_format(PixelFormat::Rgba8);
+    let mut out = format!("P6\n{} {}\n255\n", image.width, image.height).into_bytes();
+    for row in rgba.rows() {
+        for pixel in row.chunks_exact(4) {
+            let alpha = pixel[3] as u32;
+            if rgba.premultiplied {
+                out.extend_from_slice(&pixel[..3]);
+            } else {
+                out.extend(pixel[..3].iter().map(|c| (*c as u32 * alpha / 255) as u8));
+            }
+        }
+    }
+    out
+}
+
+fn qoi(image: &Image) -> Result<Vec<u8>, EncodeError> {
+    qoi::encode_to_vec(straight_rgba_rows(image), image.width, image.height).map_err(|e| match e {
+        qoi::Error::InvalidImageDimensions { width, height } => {
+            EncodeError::TooLarge { width, height }
+        }
+        qoi::Error::IoError(e) => EncodeError::Io(e),
+        _ => EncodeError::InvalidBuffer,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, height: u32, pixel: [u8; 4]) -> Image {
+        Image {
+            width,
+            height,
+            stride: width * 4,
+            format: PixelFormat::Rgba8,
+            premultiplied: false,
+            data: pixel.repeat((width * height) as usize),
+        }
+    }
+
+    #[test]
+    fn empty_image() {
+        assert!(matches!(
+            image(0, 0, [0; 4]).encode(ImageFormat::Png),
+            Err(EncodeError::Empty)
+        ));
+    }
+
+    #[test]
+    fn png_structure() {
+        let png = image(16, 16, [255, 0, 0, 255])
+            .encode(ImageFormat::Png)
+            .unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[png.len() - 12..], b"\0\0\0\0IEND\xAE\x42\x60\x82");
+    }
+
+    #[test]
+    fn bmp_structure() {
+        let bmp = image(3, 2, [1, 2, 3, 255])
+            .encode(ImageFormat::Bmp)
+            .unwrap();
+        assert_eq!(&bmp[..2], b"BM");
+        assert_eq!(bmp.len(), 54 + 3 * 2 * 4);
+        assert_eq!(&bmp[54..58], &[3, 2, 1, 255]);
+    }
+
+    #[test]
+    fn ppm_structure() {
+        let ppm = image(2, 1, [10, 20, 30, 255])
+            .encode(ImageFormat::Ppm)
+            .unwrap();
+        assert_eq!(ppm, b"P6\n2 1\n255\n\x0a\x14\x1e\x0a\x14\x1e".to_vec());
+    }
+
+    #[test]
+    fn qoi_roundtrip() {
+        let image = image(4, 4, [10, 20, 30, 128]);
+        let qoi = image.encode(ImageFormat::Qoi).unwrap();
+        assert_eq!(&qoi[..4], b"qoif");
+        let (header, data) = qoi::decode_to_vec(&qoi).unwrap();
+        assert_eq!((header.width, header.height), (4, 4));
+        assert_eq!(data, image.data);
+    }
+}
@@ -0,0 +1,136 @@
+use ultralight_sys::{
+    ulCreateKeyEvent, ulCreateMouseEvent, ulDestroyKeyEvent, ulDestroyMouseEvent, ULKeyEventType,
+    ULMouseButton, ULMouseEventType,
+};
+
+use crate::ULString;
+
+pub type KeyEventType = ULKeyEventType;
+pub type MouseEventType = ULMouseEventType;
+pub type MouseButton = ULMouseButton;
+
+/// Modifier flags for [KeyEvent::modifiers].
+pub mod modifiers {
+    pub const ALT: u32 = 1 << 0;
+    pub const CTRL: u32 = 1 << 1;
+    pub const META: u32 = 1 << 2;
+    pub const SHIFT: u32 = 1 << 3;
+}
+
+/// Virtual key codes (same as the Windows key codes) for [KeyEvent::virtual_key_code].
+pub mod key_codes {
+    pub const BACK: i32 = 0x08;
+    pub const TAB: i32 = 0x09;
+    pub const RETURN: i32 = 0x0D;
+    pub const ESCAPE: i32 = 0x1B;
+    pub const SPACE: i32 = 0x20;
+    pub const END: i32 = 0x23;
+    pub const HOME: i32 = 0x24;
+    pub const LEFT: i32 = 0x25;
+    pub const UP: i32 = 0x26;
+    pub const RIGHT: i32 = 0x27;
+    pub const DOWN: i32 = 0x28;
+    pub const DELETE: i32 = 0x2E;
+}
+
+/// A keyboard event, to be fired with [crate::View::fire_key_event].
+#[derive(Debug, Clone)]
+pub struct KeyEvent {
+    pub event_type: KeyEventType,
+    /// Bit flags from [modifiers].
+    pub modifiers: u32,
+    /// Virtual key code, see [key_codes].
+    pub virtual_key_code: i32,
+    pub native_key_code: i32,
+    /// The text generated by this event (only used by [KeyEventType::kKeyEventType_Char] events).
+    pub text: String,
+    pub unmodified_text: String,
+    pub is_keypad: bool,
+    pub is_auto_repeat: bool,
+    pub is_system_key: bool,
+}
+
+impl KeyEvent {
+    /// Create an event with no text nor modifiers.
+    pub fn new(event_type: KeyEventType, virtual_key_code: i32) -> Self {
+        KeyEvent {
+            event_type,
+            modifiers: 0,
+            virtual_key_code,
+            native_key_code: 0,
+            text: String::new(),
+            unmodified_text: String::new(),
+            is_keypad: false,
+            is_auto_repeat: false,
+            is_system_key: false,
+        }
+    }
+
+    /// A key press (before any text is generated).
+    pub fn raw_key_down(virtual_key_code: i32) -> Self {
+        KeyEvent::new(KeyEventType::kKeyEventType_RawKeyDown, virtual_key_code)
+    }
+
+    /// A key release.
+    pub fn key_up(virtual_key_code: i32) -> Self {
+        KeyEvent::new(KeyEventType::kKeyEventType_KeyUp, virtual_key_code)
+    }
+
+    /// A character input event, this is what actually inserts text in the focused element.
+    pub fn char(c: char) -> Self {
+        let mut event = KeyEvent::new(KeyEventType::kKeyEventType_Char, 0);
+        event.text = c.to_string();
+        event.unmodified_text = event.text.clone();
+        event
+    }
+
+    pub(crate) unsafe fn create(&self) -> ultralight_sys::ULKeyEvent {
+        let text = ULString::from(self.text.as_str());
+        let unmodified_text = ULString::from(self.unmodified_text.as_str());
+        // The event copies the strings, they only need to live until it is created
+        ulCreateKeyEvent(
+            self.event_type,
+            self.modifiers,
+            self.virtual_key_code,
+            self.native_key_code,
+            text.raw(),
+            unmodified_text.raw(),
+            self.is_keypad,
+            self.is_auto_repeat,
+            self.is_system_key,
+        )
+    }
+
+    pub(crate) unsafe fn destroy(raw: ultralight_sys::ULKeyEvent) {
+        ulDestroyKeyEvent(raw);
+    }
+}
+
+/// A mouse event, to be fired with [crate::View::fire_mouse_event].
+#[derive(Debug, Copy, Clone)]
+pub struct MouseEvent {
+    pub event_type: MouseEventType,
+    /// Position relative to the View, in pixels.
+    pub x: i32,
+    pub y: i32,
+    pub button: MouseButton,
+}
+
+impl MouseEvent {
+    pub fn new(event_type: MouseEventType, x: i32, y: i32, button: MouseButton) -> Self {
+        MouseEvent {
+            event_type,
+            x,
+            y,
+            button,
+        }
+    }
+
+    pub(crate) unsafe fn create(&self) -> ultralight_sys::ULMouseEvent {
+        ulCreateMouseEvent(self.event_type, self.x, self.y, self.button)
+    }
+
+    pub(crate) unsafe fn destroy(raw: ultralight_sys::ULMouseEvent) {
+        ulDestroyMouseEvent(raw);
+    }
+}
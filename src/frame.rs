@@ -0,0 +1,107 @@
+use ultralight_sys::ULIntRect;
+
+use crate::image::clamp_rect;
+use crate::{Image, Surface, View};
+
+/// Pixels of a surface that changed since the previous [Frame].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    /// Position of the changed region in the surface.
+    pub x: u32,
+    pub y: u32,
+    /// Size of the whole surface.
+    pub surface_width: u32,
+    pub surface_height: u32,
+    /// Whether or not this frame covers the whole surface (first frame, resize, [FrameGrabber::reset]).
+    pub full: bool,
+    /// Premultiplied BGRA pixels of the changed region.
+    pub image: Image,
+}
+
+impl Frame {
+    /// The changed region in the surface.
+    pub fn rect(&self) -> ULIntRect {
+        ULIntRect {
+            left: self.x as i32,
+            top: self.y as i32,
+            right: (self.x + self.image.width) as i32,
+            bottom: (self.y + self.image.height) as i32,
+        }
+    }
+}
+
+/// Extract incremental frames from a [Surface] using its dirty bounds.
+///
+/// Call [FrameGrabber::grab] after each [Renderer::render](crate::Renderer::render),
+/// only the pixels painted since the previous call are copied out and the dirty bounds are cleared.
+///
+/// ```no_run
+/// # use ultralight_rs::{FrameGrabber, Headless};
+/// # let mut headless = Headless::builder().build();
+/// let mut grabber = FrameGrabber::new();
+/// loop {
+///     headless.tick();
+///     if let Some(frame) = grabber.grab_view(&headless.view) {
+///         // Send frame.image at (frame.x, frame.y) to the client
+///     }
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct FrameGrabber {
+    size: Option<(u32, u32)>,
+}
+
+impl FrameGrabber {
+    pub fn new() -> Self {
+        FrameGrabber::default()
+    }
+
+    /// Make the next grabbed frame cover the whole surface, e.g. when a new client connects.
+    pub fn reset(&mut self) {
+        self.size = None;
+    }
+
+    /// Copy the dirty region of the surface and clear its dirty bounds.
+    /// Returns `None` if nothing was painted since the previous frame.
+    ///
+    /// The first frame, and the first one after the surface has been resized, covers the whole surface.
+    pub fn grab(&mut self, surface: &mut Surface) -> Option<Frame> {
+        let (width, height) = (surface.width(), surface.height());
+        let full = self.size != Some((width, height));
+        let rect = if full {
+            ULIntRect {
+                left: 0,
+                top: 0,
+                right: width as i32,
+                bottom: height as i32,
+            }
+        } else {
+            clamp_rect(surface.get_dirty_bounds(), width, height)
+        };
+        if rect.right <= rect.left || rect.bottom <= rect.top {
+            return None;
+        }
+
+        let image = Image::from_surface(surface, rect);
+        surface.clear_dirty_bounds();
+        self.size = Some((width, height));
+        Some(Frame {
+            x: rect.left as u32,
+            y: rect.top as u32,
+            surface_width: width,
+            surface_height: height,
+            full,
+            image,
+        })
+    }
+
+    /// Grab a frame from the surface of a CPU-rendered view.
+    /// Returns `None` if nothing changed or the view has no surface.
+    pub fn grab_view(&mut self, view: &View) -> Option<Frame> {
+        let mut surface = view.surface();
+        if surface.raw.is_null() {
+            return None;
+        }
+        self.grab(&mut surface)
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{platform, Config, Renderer, Session, View};
+
+/// A [Renderer] and a single CPU-rendered [View], without any App or Window.
+///
+/// This doesn't need a display nor a GPU, making it suitable to render pages on CI.
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use ultralight_rs::Headless;
+/// let mut headless = Headless::builder().size(1280, 720).build();
+/// headless
+///     .view
+///     .load_html_and_wait(&headless.renderer, "<h1>Hello</h1>", Duration::from_secs(5))
+///     .unwrap();
+/// headless.tick();
+/// ```
+pub struct Headless {
+    // Fields are dropped in declaration order, the view must go before the renderer.
+    pub view: View,
+    pub session: Session,
+    pub renderer: Renderer,
+}
+
+impl Headless {
+    /// Start configuring a headless renderer.
+    pub fn builder() -> HeadlessBuilder {
+        HeadlessBuilder::default()
+    }
+
+    /// Update timers and dispatch callbacks, then render the view.
+    pub fn tick(&mut self) {
+        self.renderer.update();
+        self.renderer.render();
+    }
+}
+
+/// Builder for [Headless].
+pub struct HeadlessBuilder {
+    width: u32,
+    height: u32,
+    transparent: bool,
+    filesystem_path: Option<String>,
+    logger: bool,
+    config: Option<Config>,
+}
+
+impl Default for HeadlessBuilder {
+    fn default() -> Self {
+        HeadlessBuilder {
+            width: 800,
+            height: 600,
+            transparent: false,
+            filesystem_path: None,
+            logger: true,
+            config: None,
+        }
+    }
+}
+
+impl HeadlessBuilder {
+    /// Size of the view, in pixels. Defaults to 800x600.
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Whether or not the view should support transparency. Defaults to false.
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    /// Base directory used to resolve file:/// URLs with the platform filesystem.
+    /// Defaults to `None`, which leaves the filesystem alone
+    /// (e.g. when a custom one has been set with [platform::set_filesystem]).
+    pub fn filesystem(mut self, path: Option<&str>) -> Self {
+        self.filesystem_path = path.map(str::to_string);
+        self
+    }
+
+    /// Whether or not to forward the library and the view console messages to the `log` crate.
+    /// Defaults to true.
+    pub fn logger(mut self, logger: bool) -> Self {
+        self.logger = logger;
+        self
+    }
+
+    /// Config to create the renderer with.
+    /// The GPU renderer is always disabled.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Set up the platform handlers and create the renderer and the view.
+    ///
+    /// Like [Renderer::new], this should only be called once per process lifetime.
+    pub fn build(self) -> Headless {
+        platform::enable_fontloader();
+        if let Some(path) = &self.filesystem_path {
+            platform::enable_default_filesystem(path);
+        }
+        if self.logger {
+            platform::enable_default_logger();
+        }
+
+        let config = self.config.unwrap_or_else(Config::new);
+        config.use_gpu_renderer(false);
+        let renderer = Renderer::new(&config);
+        let session = Session::default(&renderer);
+        let mut view = View::new(
+            &renderer,
+            self.width,
+            self.height,
+            self.transparent,
+            &session,
+            true,
+        );
+        if self.logger {
+            view.enable_default_logger();
+        }
+
+        Headless {
+            view,
+            session,
+            renderer,
+        }
+    }
+}
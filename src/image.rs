@@ -0,0 +1,120 @@
+use ultralight_sys::ULIntRect;
+
+use crate::pixels;
+use crate::Surface;
+
+/// Channel order of a 32-bit [Image].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// Blue, green, red, alpha. This is the native format of surfaces and bitmaps.
+    Bgra8,
+    /// Red, green, blue, alpha.
+    Rgba8,
+}
+
+/// An owned 32-bit image buffer, detached from any Ultralight object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    /// Number of bytes between rows.
+    pub stride: u32,
+    pub format: PixelFormat,
+    /// Whether or not the color channels are premultiplied by alpha.
+    pub premultiplied: bool,
+    pub data: Vec<u8>,
+}
+
+impl Image {
+    /// Copy a region of a surface. The region is clamped to the surface bounds.
+    pub fn from_surface(surface: &Surface, rect: ULIntRect) -> Self {
+        let rect = clamp_rect(rect, surface.width(), surface.height());
+        let width = (rect.right - rect.left) as u32;
+        let height = (rect.bottom - rect.top) as u32;
+        let row_bytes = surface.row_bytes() as usize;
+
+        let data = if width == 0 || height == 0 {
+            Vec::new()
+        } else {
+            let guard = surface.lock_pixels();
+            unsafe {
+                let start = (guard.pixels as *const u8)
+                    .add(rect.top as usize * row_bytes + rect.left as usize * 4);
+                pixels::copy_rows(start, row_bytes, width as usize * 4, height as usize)
+            }
+        };
+
+        Image {
+            width,
+            height,
+            stride: width * 4,
+            format: PixelFormat::Bgra8,
+            premultiplied: true,
+            data,
+        }
+    }
+
+    /// Get the pixel at (x, y), channels in the order of [Image::format].
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = (y * self.stride + x * 4) as usize;
+        [
+            self.data[i],
+            self.data[i + 1],
+            self.data[i + 2],
+            self.data[i + 3],
+        ]
+    }
+
+    /// Iterate over the rows of the image, without the padding.
+    pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        let row_len = self.width as usize * 4;
+        self.data
+            .chunks((self.stride as usize).max(1))
+            .map(move |row| &row[..row_len])
+    }
+
+    /// Convert the image to the given channel order.
+    pub fn to_format(mut self, format: PixelFormat) -> Self {
+        if self.format != format {
+            pixels::swap_red_blue(&mut self.data);
+            self.format = format;
+        }
+        self
+    }
+
+    /// Convert the image to straight (non premultiplied) alpha.
+    pub fn unpremultiplied(mut self) -> Self {
+        if self.premultiplied {
+            pixels::unpremultiply(&mut self.data);
+            self.premultiplied = false;
+        }
+        self
+    }
+
+    /// Convert to an `image` crate buffer with straight alpha.
+    #[cfg(feature = "image")]
+    pub fn to_rgba_image(&self) -> ::image::RgbaImage {
+        let rgba = self.clone().to_format(PixelFormat::Rgba8).unpremultiplied();
+        let data = rgba.rows().flatten().copied().collect();
+        ::image::RgbaImage::from_raw(self.width, self.height, data).unwrap()
+    }
+}
+
+#[cfg(feature = "image")]
+impl From<&Image> for ::image::RgbaImage {
+    fn from(image: &Image) -> Self {
+        image.to_rgba_image()
+    }
+}
+
+/// Restrict a rect to the (0, 0, width, height) area.
+pub(crate) fn clamp_rect(rect: ULIntRect, width: u32, height: u32) -> ULIntRect {
+    let left = rect.left.max(0).min(width as i32);
+    let top = rect.top.max(0).min(height as i32);
+    ULIntRect {
+        left,
+        top,
+        right: rect.right.max(left).min(width as i32),
+        bottom: rect.bottom.max(top).min(height as i32),
+    }
+}
@@ -260,25 +260,20 @@ where
     (closure as *mut F as *mut c_void, trampoline::<F>)
 }
 
-pub unsafe extern "C" fn log_forward_cb(
-    _user_data: *mut c_void,
-    _caller: ULView,
-    source: ULMessageSource,
-    level: ULMessageLevel,
-    message: ultralight_sys::ULString,
-    line_number: c_uint,
-    column_number: c_uint,
-    source_id: ultralight_sys::ULString,
-) {
-    let level = match level {
+/// `log` level of a console message.
+pub(crate) fn console_level(level: ULMessageLevel) -> Level {
+    match level {
         ULMessageLevel::kMessageLevel_Error => Level::Error,
         ULMessageLevel::kMessageLevel_Warning => Level::Warn,
         ULMessageLevel::kMessageLevel_Info => Level::Info,
         ULMessageLevel::kMessageLevel_Debug => Level::Debug,
         ULMessageLevel::kMessageLevel_Log => Level::Trace,
-    };
+    }
+}
 
-    let target = match source {
+/// Name of the source of a console message.
+pub(crate) fn console_source(source: ULMessageSource) -> &'static str {
+    match source {
         ULMessageSource::kMessageSource_XML => "xml",
         ULMessageSource::kMessageSource_JS => "js",
         ULMessageSource::kMessageSource_Network => "network",
@@ -290,11 +285,22 @@ pub unsafe extern "C" fn log_forward_cb(
         ULMessageSource::kMessageSource_Security => "security",
         ULMessageSource::kMessageSource_ContentBlocker => "contentblocker",
         ULMessageSource::kMessageSource_Other => "other",
-    };
+    }
+}
 
+pub unsafe extern "C" fn log_forward_cb(
+    _user_data: *mut c_void,
+    _caller: ULView,
+    source: ULMessageSource,
+    level: ULMessageLevel,
+    message: ultralight_sys::ULString,
+    line_number: c_uint,
+    column_number: c_uint,
+    source_id: ultralight_sys::ULString,
+) {
     log::log!(
-        target: target,
-        level,
+        target: console_source(source),
+        console_level(level),
         "({}, {}, {}) {}",
         Into::<String>::into(ULString::from(source_id)),
         line_number,
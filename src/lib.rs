@@ -5,33 +5,54 @@ use ultralight_sys::{ulVersionMajor, ulVersionMinor, ulVersionPatch, ulVersionSt
 pub use crate::app::*;
 pub use crate::bitmap::*;
 pub use crate::config::*;
+pub use crate::decode::*;
+pub use crate::encode::*;
+pub use crate::event::*;
+pub use crate::frame::*;
+pub use crate::headless::*;
+pub use crate::image::*;
 pub use crate::monitor::*;
 pub use crate::overlay::*;
+pub use crate::recorder::*;
 pub use crate::renderer::*;
 pub use crate::session::*;
 pub use crate::settings::*;
 pub use crate::string::*;
 pub use crate::surface::*;
 pub use crate::view::*;
+pub use crate::wait::*;
 pub use crate::window::*;
 
 mod app;
+/// Browser-like automation of a [View] : query elements and interact with them.
+pub mod automation;
 mod bitmap;
 mod config;
+mod decode;
+/// Visual regression testing : compare renderings to golden images.
+pub mod diff;
+mod encode;
+mod event;
+mod frame;
+mod headless;
 pub mod helpers;
+mod image;
 mod internal;
 /// JavascriptCore bindings.
 pub mod jsc;
 mod monitor;
 mod overlay;
+mod pixels;
 /// Functions that control Ultralight environment like filesystem and clipboard.
 pub mod platform;
+mod recorder;
 mod renderer;
 mod session;
 mod settings;
 mod string;
 mod surface;
 mod view;
+mod wait;
 mod window;
 
 /// Get the numeric major version of the library.
@@ -0,0 +1,83 @@
+//! Pixel manipulation helpers on raw 32-bit buffers.
+
+/// Swap the first and third channel of every 4-byte pixel (BGRA <-> RGBA).
+pub fn swap_red_blue(data: &mut [u8]) {
+    for pixel in data.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
+}
+
+/// Convert premultiplied alpha to straight alpha, the alpha channel being the last one.
+pub fn unpremultiply(data: &mut [u8]) {
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u32;
+        if alpha != 0 && alpha != 255 {
+            for c in &mut pixel[..3] {
+                *c = ((*c as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+            }
+        }
+    }
+}
+
+/// Convert straight alpha to premultiplied alpha, the alpha channel being the last one.
+pub fn premultiply(data: &mut [u8]) {
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u32;
+        if alpha != 255 {
+            for c in &mut pixel[..3] {
+                *c = ((*c as u32 * alpha + 127) / 255) as u8;
+            }
+        }
+    }
+}
+
+/// Copy `height` rows of `row_len` bytes from a strided buffer into a tightly packed one.
+///
+/// # Safety
+/// `src` must be valid for reads of `src_stride * (height - 1) + row_len` bytes.
+pub unsafe fn copy_rows(
+    src: *const u8,
+    src_stride: usize,
+    row_len: usize,
+    height: usize,
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(row_len * height);
+    for y in 0..height {
+        let row = std::slice::from_raw_parts(src.add(y * src_stride), row_len);
+        data.extend_from_slice(row);
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swap_channels() {
+        let mut data = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        swap_red_blue(&mut data);
+        assert_eq!(data, vec![3, 2, 1, 4, 7, 6, 5, 8]);
+    }
+
+    #[test]
+    fn unpremultiply_alpha() {
+        let mut data = vec![100, 50, 25, 128, 10, 20, 30, 255, 0, 0, 0, 0];
+        unpremultiply(&mut data);
+        assert_eq!(data, vec![199, 100, 50, 128, 10, 20, 30, 255, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn premultiply_alpha() {
+        let mut data = vec![200, 100, 50, 128, 10, 20, 30, 255, 90, 90, 90, 0];
+        premultiply(&mut data);
+        assert_eq!(data, vec![100, 50, 25, 128, 10, 20, 30, 255, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn copy_strided_rows() {
+        let src = [1u8, 2, 0, 0, 3, 4, 0, 0];
+        let data = unsafe { copy_rows(src.as_ptr(), 4, 2, 2) };
+        assert_eq!(data, vec![1, 2, 3, 4]);
+    }
+}
@@ -0,0 +1,756 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use flate2::read::{DeflateDecoder, GzDecoder};
+use flate2::Crc;
+
+use crate::platform::{File, Filesystem};
+
+/// Maximum size of the GNU long name and pax headers.
+const MAX_METADATA_SIZE: u64 = 64 * 1024;
+
+/// How an entry is stored in the archive.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Compression {
+    Stored,
+    Deflate,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    /// Offset of the entry data in the archive.
+    offset: u64,
+    /// Size of the data in the archive.
+    stored_size: u64,
+    /// Size of the file.
+    size: u64,
+    compression: Compression,
+    crc: Option<u32>,
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason.to_string())
+}
+
+fn le_u16(data: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([data[at], data[at + 1]])
+}
+
+fn le_u32(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+}
+
+/// Paths are stored relative to the root, without leading `/` or `./`.
+fn normalize(path: &str) -> &str {
+    let mut path = path.trim_start_matches('/');
+    while let Some(rest) = path.strip_prefix("./") {
+        path = rest.trim_start_matches('/');
+    }
+    path
+}
+
+/// A [Filesystem] serving the files of a zip or tar archive.
+///
+/// Entries are indexed when the archive is mounted, the archive itself stays on disk (or wherever
+/// the reader reads from). Files are streamed from the archive, deflated zip entries are
+/// decompressed while reading.
+/// `.tar.gz` archives are decompressed while reading too, but from the start of the archive :
+/// seeking backwards, or opening a file before the last one read, decompresses the archive again
+/// up to that point.
+///
+/// ```no_run
+/// # use ultralight_rs::platform::{self, ArchiveFilesystem};
+/// # fn test() -> std::io::Result<()> {
+/// platform::set_filesystem_impl(ArchiveFilesystem::open("themes/dark.zip")?);
+/// # Ok(())
+/// # }
+/// ```
+pub struct ArchiveFilesystem {
+    source: Arc<Mutex<Box<dyn File>>>,
+    entries: BTreeMap<String, Entry>,
+}
+
+impl ArchiveFilesystem {
+    /// Mount an archive file, its format is detected from its contents.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        ArchiveFilesystem::new(fs::File::open(path)?)
+    }
+
+    /// Mount an archive, its format (zip, tar or gzipped tar) is detected from its contents.
+    pub fn new<R: File + 'static>(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        let read = reader.read(&mut magic)?;
+        reader.seek(SeekFrom::Start(0))?;
+        match &magic[..read] {
+            [0x1F, 0x8B, ..] => {
+                let len = reader.seek(SeekFrom::End(0))?;
+                let source = Section {
+                    source: Arc::new(Mutex::new(Box::new(reader))),
+                    start: 0,
+                    len,
+                    pos: 0,
+                };
+                ArchiveFilesystem::tar(Inflater::new(source, Format::Gzip, None, None))
+            }
+            b"PK\x03\x04" | b"PK\x05\x06" => ArchiveFilesystem::zip(reader),
+            _ => ArchiveFilesystem::tar(reader),
+        }
+    }
+
+    /// Mount a zip archive. Zip64 and encrypted archives aren't supported.
+    pub fn zip<R: File + 'static>(mut reader: R) -> io::Result<Self> {
+        let entries = zip_entries(&mut reader)?;
+        Ok(ArchiveFilesystem {
+            source: Arc::new(Mutex::new(Box::new(reader))),
+            entries,
+        })
+    }
+
+    /// Mount a tar archive (ustar, with GNU and pax long names).
+    pub fn tar<R: File + 'static>(mut reader: R) -> io::Result<Self> {
+        let entries = tar_entries(&mut reader)?;
+        Ok(ArchiveFilesystem {
+            source: Arc::new(Mutex::new(Box::new(reader))),
+            entries,
+        })
+    }
+
+    /// Paths of all the files in the archive.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    /// Uncompressed size of a file.
+    pub fn size(&self, path: &str) -> Option<u64> {
+        self.entries.get(normalize(path)).map(|e| e.size)
+    }
+
+    fn section(&self, entry: &Entry) -> Section {
+        Section {
+            source: self.source.clone(),
+            start: entry.offset,
+            len: entry.stored_size,
+            pos: 0,
+        }
+    }
+}
+
+impl Filesystem for ArchiveFilesystem {
+    fn file_exists(&self, path: &str) -> bool {
+        self.entries.contains_key(normalize(path))
+    }
+
+    fn open(&self, path: &str) -> io::Result<Box<dyn File>> {
+        let entry = self
+            .entries
+            .get(normalize(path))
+            .ok_or(io::ErrorKind::NotFound)?;
+        let section = self.section(entry);
+        match entry.compression {
+            Compression::Stored => Ok(Box::new(section)),
+            Compression::Deflate => Ok(Box::new(Inflater::new(
+                section,
+                Format::Deflate,
+                Some(entry.size),
+                entry.crc,
+            ))),
+        }
+    }
+}
+
+/// Part of the archive, reading through the shared archive reader.
+#[derive(Clone)]
+struct Section {
+    source: Arc<Mutex<Box<dyn File>>>,
+    start: u64,
+    len: u64,
+    pos: u64,
+}
+
+impl Read for Section {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len.saturating_sub(self.pos);
+        let max = (buf.len() as u64).min(remaining) as usize;
+        if max == 0 {
+            return Ok(0);
+        }
+        let mut source = self.source.lock().unwrap();
+        source.seek(SeekFrom::Start(self.start + self.pos))?;
+        let read = source.read(&mut buf[..max])?;
+        self.pos += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for Section {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        };
+        self.pos = pos.ok_or_else(|| invalid("seek before the start of the file"))?;
+        Ok(self.pos)
+    }
+}
+
+/// Compressed stream format.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Format {
+    Deflate,
+    Gzip,
+}
+
+/// Decompresses a [Section] while reading.
+///
+/// Seeking forward decompresses up to the new position, seeking backwards starts again
+/// from the beginning of the stream.
+struct Inflater {
+    source: Section,
+    format: Format,
+    decoder: Box<dyn Read + Send>,
+    /// Expected size of the decompressed data, unknown for gzip archives.
+    size: Option<u64>,
+    /// Expected CRC-32 of the decompressed data, checked once everything has been read.
+    crc: Option<u32>,
+    hasher: Crc,
+    pos: u64,
+}
+
+impl Inflater {
+    fn new(source: Section, format: Format, size: Option<u64>, crc: Option<u32>) -> Self {
+        let mut inflater = Inflater {
+            decoder: Box::new(io::empty()),
+            source,
+            format,
+            size,
+            crc,
+            hasher: Crc::new(),
+            pos: 0,
+        };
+        inflater.restart();
+        inflater
+    }
+
+    fn restart(&mut self) {
+        let source = self.source.clone();
+        // The gzip decoder checks the CRC-32 and size of its trailer itself
+        self.decoder = match self.format {
+            Format::Deflate => Box::new(DeflateDecoder::new(source)),
+            Format::Gzip => Box::new(GzDecoder::new(source)),
+        };
+        self.hasher = Crc::new();
+        self.pos = 0;
+    }
+
+    /// Check that the stream ends at the expected size with the expected checksum.
+    fn check_end(&mut self) -> io::Result<()> {
+        if self.decoder.read(&mut [0])? != 0 || self.crc.is_some_and(|crc| crc != self.hasher.sum())
+        {
+            return Err(invalid("corrupted archive entry"));
+        }
+        Ok(())
+    }
+}
+
+impl Read for Inflater {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self
+            .size
+            .map_or(u64::MAX, |size| size.saturating_sub(self.pos));
+        let max = (buf.len() as u64).min(remaining) as usize;
+        if max == 0 {
+            return Ok(0);
+        }
+        let read = self.decoder.read(&mut buf[..max])?;
+        if read == 0 && self.size.is_some() {
+            return Err(invalid("truncated archive entry"));
+        }
+        self.hasher.update(&buf[..read]);
+        self.pos += read as u64;
+        if Some(self.pos) == self.size {
+            self.check_end()?;
+        }
+        Ok(read)
+    }
+}
+
+impl Seek for Inflater {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => {
+                let size = self.size.ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::Unsupported,
+                        "seek from the end of a gzip stream",
+                    )
+                })?;
+                size.checked_add_signed(offset)
+            }
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        };
+        let pos = pos.ok_or_else(|| invalid("seek before the start of the file"))?;
+        if pos < self.pos {
+            self.restart();
+        }
+        let skip = pos - self.pos;
+        let skipped = io::copy(&mut self.by_ref().take(skip), &mut io::sink())?;
+        // Seeking past the end is allowed, reads return nothing
+        self.pos += skip - skipped;
+        Ok(self.pos)
+    }
+}
+
+fn zip_entries(reader: &mut dyn File) -> io::Result<BTreeMap<String, Entry>> {
+    // The end of central directory record is at the end, before an optional comment
+    let archive_len = reader.seek(SeekFrom::End(0))?;
+    let tail_len = archive_len.min(22 + 0xFFFF);
+    reader.seek(SeekFrom::Start(archive_len - tail_len))?;
+    let mut tail = vec![0; tail_len as usize];
+    reader.read_exact(&mut tail)?;
+    let end = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&i| tail[i..i + 4] == *b"PK\x05\x06")
+        .ok_or_else(|| invalid("zip end of central directory not found"))?;
+    let count = le_u16(&tail, end + 10) as usize;
+    let directory_size = le_u32(&tail, end + 12);
+    let directory_offset = le_u32(&tail, end + 16);
+    if count == 0xFFFF || directory_size == 0xFFFF_FFFF || directory_offset == 0xFFFF_FFFF {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "zip64 archives"));
+    }
+    // The directory is before the end record
+    if directory_offset as u64 + directory_size as u64 > archive_len - tail_len + end as u64 {
+        return Err(invalid("corrupted zip end of central directory"));
+    }
+
+    reader.seek(SeekFrom::Start(directory_offset as u64))?;
+    let mut directory = vec![0; directory_size as usize];
+    reader.read_exact(&mut directory)?;
+
+    let mut entries = BTreeMap::new();
+    let mut pos = 0;
+    for _ in 0..count {
+        let header = directory
+            .get(pos..pos + 46)
+            .filter(|h| h[..4] == *b"PK\x01\x02")
+            .ok_or_else(|| invalid("corrupted zip central directory"))?;
+        let flags = le_u16(header, 8);
+        let method = le_u16(header, 10);
+        let crc = le_u32(header, 16);
+        let stored_size = le_u32(header, 20) as u64;
+        let size = le_u32(header, 24) as u64;
+        let name_len = le_u16(header, 28) as usize;
+        let extra_len = le_u16(header, 30) as usize;
+        let comment_len = le_u16(header, 32) as usize;
+        let local_offset = le_u32(header, 42) as u64;
+        let name = directory
+            .get(pos + 46..pos + 46 + name_len)
+            .ok_or_else(|| invalid("corrupted zip central directory"))?;
+        let name = String::from_utf8_lossy(name).replace('\\', "/");
+        pos += 46 + name_len + extra_len + comment_len;
+
+        if name.ends_with('/') {
+            continue;
+        }
+        let compression = match method {
+            0 => Compression::Stored,
+            8 => Compression::Deflate,
+            _ => {
+                log::warn!(
+                    "Skipping zip entry '{}' : unsupported compression method {}",
+                    name,
+                    method
+                );
+                continue;
+            }
+        };
+        if flags & 1 != 0 {
+            log::warn!("Skipping zip entry '{}' : encrypted", name);
+            continue;
+        }
+
+        // The data follows the local header, which has its own name and extra fields
+        let mut local = [0; 30];
+        reader.seek(SeekFrom::Start(local_offset))?;
+        reader.read_exact(&mut local)?;
+        if local[..4] != *b"PK\x03\x04" {
+            return Err(invalid("corrupted zip local header"));
+        }
+        let offset = local_offset + 30 + le_u16(&local, 26) as u64 + le_u16(&local, 28) as u64;
+        if offset + stored_size > archive_len {
+            return Err(invalid("zip entry past the end of the archive"));
+        }
+        entries.insert(
+            normalize(&name).to_string(),
+            Entry {
+                offset,
+                stored_size,
+                size,
+                compression,
+                crc: Some(crc),
+            },
+        );
+    }
+    Ok(entries)
+}
+
+/// Parse a numeric tar field, octal or GNU base-256.
+fn tar_number(field: &[u8]) -> io::Result<u64> {
+    if field[0] & 0x80 != 0 {
+        return Ok(field[1..]
+            .iter()
+            .fold((field[0] & 0x7F) as u64, |n, &b| (n << 8) | b as u64));
+    }
+    let text = String::from_utf8_lossy(field);
+    let text = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8).map_err(|_| invalid("corrupted tar header"))
+}
+
+fn tar_string(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// Get the `path` record of a pax extended header.
+fn pax_path(data: &[u8]) -> Option<String> {
+    let mut rest = data;
+    while !rest.is_empty() {
+        let space = rest.iter().position(|&b| b == b' ')?;
+        let len: usize = std::str::from_utf8(&rest[..space]).ok()?.parse().ok()?;
+        let record = rest.get(space + 1..len)?;
+        let record = record.strip_suffix(b"\n").unwrap_or(record);
+        if let Some(path) = record.strip_prefix(b"path=") {
+            return Some(String::from_utf8_lossy(path).into_owned());
+        }
+        rest = &rest[len..];
+    }
+    None
+}
+
+fn tar_entries(reader: &mut dyn File) -> io::Result<BTreeMap<String, Entry>> {
+    let mut entries = BTreeMap::new();
+    // Gzip streams don't know their length, entries are checked by reading their last byte
+    let archive_len = match reader.seek(SeekFrom::End(0)) {
+        Ok(len) => Some(len),
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => None,
+        Err(e) => return Err(e),
+    };
+    let mut offset = reader.seek(SeekFrom::Start(0))?;
+    let mut long_name = None;
+    loop {
+        let mut header = [0; 512];
+        match reader.read_exact(&mut header) {
+            Ok(()) => {}
+            // Some archives don't have the end of archive blocks
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        let checksum = tar_number(&header[148..156])?;
+        let sum: u64 = header
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                if (148..156).contains(&i) {
+                    b' ' as u64
+                } else {
+                    b as u64
+                }
+            })
+            .sum();
+        if checksum != sum {
+            return Err(invalid("tar header checksum mismatch"));
+        }
+
+        let size = tar_number(&header[124..136])?;
+        let data_offset = offset + 512;
+        let out_of_bounds = || invalid("tar entry out of bounds");
+        let data_end = data_offset.checked_add(size).ok_or_else(out_of_bounds)?;
+        offset = size
+            .div_ceil(512)
+            .checked_mul(512)
+            .and_then(|padded| data_offset.checked_add(padded))
+            .ok_or_else(out_of_bounds)?;
+        match header[156] {
+            b'0' | 0 | b'7' => {
+                let in_bounds = match archive_len {
+                    Some(len) => data_end <= len,
+                    None if size == 0 => true,
+                    None => {
+                        reader.seek(SeekFrom::Start(data_end - 1))?;
+                        reader.read(&mut [0])? == 1
+                    }
+                };
+                if !in_bounds {
+                    return Err(out_of_bounds());
+                }
+                let name = long_name.take().unwrap_or_else(|| {
+                    let name = tar_string(&header[0..100]);
+                    let prefix = tar_string(&header[345..500]);
+                    if &header[257..262] == b"ustar" && !prefix.is_empty() {
+                        format!("{}/{}", prefix, name)
+                    } else {
+                        name
+                    }
+                });
+                entries.insert(
+                    normalize(&name).to_string(),
+                    Entry {
+                        offset: data_offset,
+                        stored_size: size,
+                        size,
+                        compression: Compression::Stored,
+                        crc: None,
+                    },
+                );
+            }
+            // GNU long name and pax headers give the name of the next entry
+            b'L' | b'x' => {
+                if size > MAX_METADATA_SIZE {
+                    return Err(invalid("tar extended header too large"));
+                }
+                let mut data = Vec::new();
+                (&mut *reader).take(size).read_to_end(&mut data)?;
+                if data.len() as u64 != size {
+                    return Err(out_of_bounds());
+                }
+                long_name = if header[156] == b'L' {
+                    Some(tar_string(&data))
+                } else {
+                    pax_path(&data)
+                };
+            }
+            // Directories, links and other special files
+            _ => long_name = None,
+        }
+        reader.seek(SeekFrom::Start(offset))?;
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use flate2::write::{DeflateEncoder, GzEncoder};
+    use flate2::Compression as Level;
+
+    use super::*;
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Level::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn crc32(data: &[u8]) -> u32 {
+        let mut crc = Crc::new();
+        crc.update(data);
+        crc.sum()
+    }
+
+    fn zip(files: &[(&str, &[u8], bool)]) -> Vec<u8> {
+        let mut archive = Vec::new();
+        let mut directory = Vec::new();
+        for (name, data, compress) in files {
+            let stored = if *compress {
+                deflate(data)
+            } else {
+                data.to_vec()
+            };
+            let mut header = Vec::new();
+            header.extend_from_slice(&[0, 0]); // flags
+            header.extend_from_slice(&(if *compress { 8u16 } else { 0 }).to_le_bytes());
+            header.extend_from_slice(&[0; 4]); // time, date
+            header.extend_from_slice(&crc32(data).to_le_bytes());
+            header.extend_from_slice(&(stored.len() as u32).to_le_bytes());
+            header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+
+            directory.extend_from_slice(b"PK\x01\x02\x14\x00\x14\x00");
+            directory.extend_from_slice(&header);
+            directory.extend_from_slice(&[0; 12]); // extra, comment, disk, attributes
+            directory.extend_from_slice(&(archive.len() as u32).to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+
+            archive.extend_from_slice(b"PK\x03\x04\x14\x00");
+            archive.extend_from_slice(&header);
+            archive.extend_from_slice(&[0, 0]); // extra
+            archive.extend_from_slice(name.as_bytes());
+            archive.extend_from_slice(&stored);
+        }
+        let offset = archive.len() as u32;
+        archive.extend_from_slice(&directory);
+        archive.extend_from_slice(b"PK\x05\x06\0\0\0\0");
+        archive.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        archive.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        archive.extend_from_slice(&offset.to_le_bytes());
+        archive.extend_from_slice(&[0, 0]);
+        archive
+    }
+
+    fn tar(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut archive = Vec::new();
+        for (name, data) in files {
+            let mut header = [0u8; 512];
+            header[..name.len()].copy_from_slice(name.as_bytes());
+            header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+            header[156] = b'0';
+            header[257..263].copy_from_slice(b"ustar\0");
+            header[148..156].copy_from_slice(b"        ");
+            let sum: u32 = header.iter().map(|&b| b as u32).sum();
+            header[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+            archive.extend_from_slice(&header);
+            archive.extend_from_slice(data);
+            archive.resize(archive.len().div_ceil(512) * 512, 0);
+        }
+        archive.resize(archive.len() + 1024, 0);
+        archive
+    }
+
+    fn read(fs: &ArchiveFilesystem, path: &str) -> String {
+        let mut contents = String::new();
+        fs.open(path)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        contents
+    }
+
+    #[test]
+    fn zip_archive() {
+        let html = "<p>Hello</p>".repeat(20);
+        let archive = zip(&[
+            ("index.html", html.as_bytes(), true),
+            ("css/", b"", false),
+            ("css/style.css", b"p { color: red }", false),
+        ]);
+        let fs = ArchiveFilesystem::new(Cursor::new(archive)).unwrap();
+        assert_eq!(
+            fs.paths().collect::<Vec<_>>(),
+            ["css/style.css", "index.html"]
+        );
+        assert!(!fs.file_exists("css"));
+        assert_eq!(fs.size("index.html"), Some(html.len() as u64));
+        assert_eq!(fs.mime_type("/css/style.css").unwrap(), "text/css");
+        assert_eq!(read(&fs, "index.html"), html);
+        assert_eq!(read(&fs, "css/style.css"), "p { color: red }");
+
+        let mut file = fs.open("css/style.css").unwrap();
+        assert_eq!(file.seek(SeekFrom::End(-5)).unwrap(), 11);
+        let mut end = String::new();
+        file.read_to_string(&mut end).unwrap();
+        assert_eq!(end, "red }");
+    }
+
+    #[test]
+    fn tar_archive() {
+        let archive = tar(&[
+            ("./index.html", b"<p>Hello</p>"),
+            ("js/app.js", b"console.log('hello')"),
+        ]);
+        let fs = ArchiveFilesystem::new(Cursor::new(archive)).unwrap();
+        assert_eq!(fs.paths().collect::<Vec<_>>(), ["index.html", "js/app.js"]);
+        assert_eq!(fs.size("js/app.js"), Some(20));
+        assert_eq!(fs.mime_type("js/app.js").unwrap(), "text/javascript");
+        assert_eq!(read(&fs, "index.html"), "<p>Hello</p>");
+        assert_eq!(read(&fs, "js/app.js"), "console.log('hello')");
+        assert!(fs.open("missing.css").is_err());
+    }
+
+    #[test]
+    fn streamed_entries() {
+        let html = "<p>Hello</p>".repeat(1000);
+        let mut archive = zip(&[("index.html", html.as_bytes(), true)]);
+        let fs = ArchiveFilesystem::new(Cursor::new(archive.clone())).unwrap();
+        let mut file = fs.open("index.html").unwrap();
+        let mut data = [0; 12];
+        file.seek(SeekFrom::Start(600)).unwrap();
+        file.read_exact(&mut data).unwrap();
+        assert_eq!(&data, b"<p>Hello</p>");
+        assert_eq!(file.seek(SeekFrom::End(0)).unwrap(), html.len() as u64);
+        assert_eq!(file.read(&mut data).unwrap(), 0);
+        file.seek(SeekFrom::Start(3)).unwrap();
+        file.read_exact(&mut data[..5]).unwrap();
+        assert_eq!(&data[..5], b"Hello");
+
+        // Wrong CRC-32 in the central directory, detected at the end of the file
+        let directory = archive.len() - 22 - 46 - "index.html".len();
+        archive[directory + 16] ^= 1;
+        let fs = ArchiveFilesystem::new(Cursor::new(archive)).unwrap();
+        let mut contents = Vec::new();
+        assert!(fs
+            .open("index.html")
+            .unwrap()
+            .read_to_end(&mut contents)
+            .is_err());
+    }
+
+    #[test]
+    fn gzipped_tar_archive() {
+        let archive = tar(&[
+            ("index.html", b"<p>Hello</p>"),
+            ("js/app.js", b"console.log('hello')"),
+        ]);
+        let mut encoder = GzEncoder::new(Vec::new(), Level::default());
+        encoder.write_all(&archive).unwrap();
+        let fs = ArchiveFilesystem::new(Cursor::new(encoder.finish().unwrap())).unwrap();
+        assert_eq!(fs.paths().collect::<Vec<_>>(), ["index.html", "js/app.js"]);
+        // Going back to an earlier file decompresses again
+        assert_eq!(read(&fs, "js/app.js"), "console.log('hello')");
+        assert_eq!(read(&fs, "index.html"), "<p>Hello</p>");
+    }
+
+    #[test]
+    fn untrusted_sizes() {
+        // Central directory past the end of the archive
+        let mut archive = zip(&[("index.html", b"<p>Hello</p>", false)]);
+        let end = archive.len() - 22;
+        archive[end + 12..end + 16].copy_from_slice(&0x7FFF_FFFFu32.to_le_bytes());
+        assert!(ArchiveFilesystem::new(Cursor::new(archive)).is_err());
+
+        // Entry data past the end of the archive
+        let mut archive = zip(&[("index.html", b"<p>Hello</p>", false)]);
+        let directory = archive.len() - 22 - 46 - "index.html".len();
+        archive[directory + 20..directory + 24].copy_from_slice(&0x7FFF_FFFFu32.to_le_bytes());
+        assert!(ArchiveFilesystem::new(Cursor::new(archive)).is_err());
+
+        // Huge pax header
+        let mut archive = tar(&[("pax", b"")]);
+        archive[124..135].copy_from_slice(format!("{:011o}", 1u64 << 32).as_bytes());
+        archive[156] = b'x';
+        archive[148..156].copy_from_slice(b"        ");
+        let sum: u32 = archive[..512].iter().map(|&b| b as u32).sum();
+        archive[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+        assert!(ArchiveFilesystem::new(Cursor::new(archive)).is_err());
+
+        // Entry data past the end of the archive, and a base-256 size overflowing the offsets
+        let sizes = [
+            format!("{:011o}\0", 1u64 << 32).into_bytes(),
+            vec![0xFF; 12],
+        ];
+        for size in sizes {
+            let mut archive = tar(&[("index.html", b"<p>Hello</p>")]);
+            archive[124..136].copy_from_slice(&size);
+            archive[148..156].copy_from_slice(b"        ");
+            let sum: u32 = archive[..512].iter().map(|&b| b as u32).sum();
+            archive[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+            let mut gzipped = GzEncoder::new(Vec::new(), Level::default());
+            gzipped.write_all(&archive).unwrap();
+            for archive in [archive, gzipped.finish().unwrap()] {
+                let error = ArchiveFilesystem::new(Cursor::new(archive)).err().unwrap();
+                assert_eq!(error.to_string(), "tar entry out of bounds");
+            }
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::sync::{Arc, Mutex};
+
+use ultralight_sys::{ulPlatformSetClipboard, ulStringAssignString, ULClipboard};
+
+use crate::ULString;
+
+static CLIPBOARD: Mutex<Option<Box<dyn Clipboard>>> = Mutex::new(None);
+
+/// Access to the system clipboard, used by pages to cut, copy and paste.
+pub trait Clipboard: Send + 'static {
+    /// Clear the clipboard.
+    fn clear(&self);
+
+    /// Read plain text from the clipboard.
+    fn read_plain_text(&self) -> String;
+
+    /// Write plain text to the clipboard.
+    fn write_plain_text(&self, text: &str);
+}
+
+fn with_clipboard<R>(f: impl FnOnce(&dyn Clipboard) -> R) -> Option<R> {
+    CLIPBOARD.lock().unwrap().as_deref().map(f)
+}
+
+unsafe extern "C" fn clear() {
+    with_clipboard(|c| c.clear());
+}
+
+unsafe extern "C" fn read_plain_text(result: ultralight_sys::ULString) {
+    if let Some(text) = with_clipboard(|c| c.read_plain_text()) {
+        let text = ULString::from(text.as_str());
+        ulStringAssignString(result, text.raw());
+    }
+}
+
+unsafe extern "C" fn write_plain_text(text: ultralight_sys::ULString) {
+    let text = ULString::from(text).to_string_lossy();
+    with_clipboard(|c| c.write_plain_text(&text));
+}
+
+/// Use `clipboard` as the clipboard.
+pub fn set_clipboard_impl<T: Clipboard>(clipboard: T) {
+    *CLIPBOARD.lock().unwrap() = Some(Box::new(clipboard));
+    set_clipboard(ULClipboard {
+        clear: Some(clear),
+        read_plain_text: Some(read_plain_text),
+        write_plain_text: Some(write_plain_text),
+    })
+}
+
+/// Set a custom Clipboard implementation.
+/// This should be used if you are using ulCreateRenderer()
+/// (which does not provide its own clipboard implementation).
+/// The Clipboard interface is used by the library to make calls to the
+/// system's native clipboard (eg, cut, copy, paste).
+pub fn set_clipboard(clipboard: ULClipboard) {
+    unsafe {
+        ulPlatformSetClipboard(clipboard);
+    }
+}
+
+/// A [Clipboard] keeping its contents in memory, isolated from the system clipboard.
+///
+/// Clones share the same contents, keep one to check what pages copied :
+/// ```no_run
+/// # use ultralight_rs::platform::{self, InMemoryClipboard};
+/// let clipboard = InMemoryClipboard::new();
+/// platform::set_clipboard_impl(clipboard.clone());
+/// // Select some text and press Ctrl+C...
+/// assert_eq!(clipboard.text(), "Hello");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryClipboard {
+    text: Arc<Mutex<String>>,
+}
+
+impl InMemoryClipboard {
+    pub fn new() -> Self {
+        InMemoryClipboard::default()
+    }
+
+    /// Current contents of the clipboard.
+    pub fn text(&self) -> String {
+        self.text.lock().unwrap().clone()
+    }
+
+    /// Replace the contents of the clipboard, for pages to paste.
+    pub fn set_text(&self, text: &str) {
+        *self.text.lock().unwrap() = text.to_string();
+    }
+}
+
+impl Clipboard for InMemoryClipboard {
+    fn clear(&self) {
+        self.text.lock().unwrap().clear();
+    }
+
+    fn read_plain_text(&self) -> String {
+        self.text()
+    }
+
+    fn write_plain_text(&self, text: &str) {
+        self.set_text(text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ultralight_sys::{ulCreateStringUTF16, ulDestroyString};
+
+    use super::*;
+
+    #[test]
+    fn in_memory_clipboard() {
+        let clipboard = InMemoryClipboard::new();
+        *CLIPBOARD.lock().unwrap() = Some(Box::new(clipboard.clone()));
+
+        // Through the callbacks given to Ultralight
+        let copied = ULString::new("copied");
+        unsafe { write_plain_text(copied.raw()) };
+        assert_eq!(clipboard.text(), "copied");
+
+        clipboard.set_text("to paste");
+        let mut pasted = ULString::new("");
+        unsafe { read_plain_text(pasted.raw()) };
+        assert_eq!(pasted.to_string_lossy(), "to paste");
+
+        // Lone surrogate
+        let mut invalid = [b'a' as u16, 0xD800];
+        unsafe {
+            let invalid = ulCreateStringUTF16(invalid.as_mut_ptr(), invalid.len() as u64);
+            write_plain_text(invalid);
+            ulDestroyString(invalid);
+        }
+        assert_eq!(clipboard.text(), "a\u{FFFD}");
+
+        unsafe { clear() };
+        assert_eq!(clipboard.text(), "");
+        pasted.set(&ULString::new("unchanged"));
+        *CLIPBOARD.lock().unwrap() = None;
+        unsafe { read_plain_text(pasted.raw()) };
+        assert_eq!(pasted.to_string_lossy(), "unchanged");
+    }
+}
@@ -0,0 +1,97 @@
+use std::io::{self, Cursor};
+
+use crate::platform::{File, Filesystem};
+
+#[doc(hidden)]
+pub use ultralight_macros::asset_table as __asset_table;
+
+/// Create an [EmbeddedFilesystem](crate::platform::EmbeddedFilesystem) with every file of a directory
+/// (relative to the crate manifest directory) compiled into the binary.
+///
+/// ```ignore
+/// // ui/index.html is served as file:///index.html
+/// platform::set_filesystem_impl(ultralight_rs::include_assets!("ui/"));
+/// view.load_url("file:///index.html");
+/// ```
+///
+/// Files are tracked by cargo once embedded, but adding a file to the directory
+/// won't trigger a rebuild by itself.
+#[macro_export]
+macro_rules! include_assets {
+    ($dir:literal) => {
+        $crate::platform::EmbeddedFilesystem::new($crate::platform::__asset_table!($dir))
+    };
+}
+
+/// A read-only [Filesystem] over files compiled into the binary.
+/// Use [include_assets!](crate::include_assets) to embed a whole directory.
+#[derive(Debug, Copy, Clone)]
+pub struct EmbeddedFilesystem {
+    files: &'static [(&'static str, &'static [u8])],
+}
+
+impl EmbeddedFilesystem {
+    /// Serve a table of paths (relative to the root, eg `css/style.css`) to file contents.
+    pub const fn new(files: &'static [(&'static str, &'static [u8])]) -> Self {
+        EmbeddedFilesystem { files }
+    }
+
+    /// Get the contents of a file.
+    pub fn get(&self, path: &str) -> Option<&'static [u8]> {
+        let path = path.trim_start_matches('/');
+        self.files
+            .iter()
+            .find(|(p, _)| *p == path)
+            .map(|(_, data)| *data)
+    }
+
+    /// Paths of all the embedded files.
+    pub fn paths(&self) -> impl Iterator<Item = &'static str> {
+        self.files.iter().map(|(p, _)| *p)
+    }
+}
+
+impl Filesystem for EmbeddedFilesystem {
+    fn file_exists(&self, path: &str) -> bool {
+        self.get(path).is_some()
+    }
+
+    fn open(&self, path: &str) -> io::Result<Box<dyn File>> {
+        match self.get(path) {
+            Some(data) => Ok(Box::new(Cursor::new(data))),
+            None => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    static ASSETS: EmbeddedFilesystem = EmbeddedFilesystem::new(&[
+        ("index.html", b"<link rel=stylesheet href=css/style.css>"),
+        ("css/style.css", b"p { color: red }"),
+        ("LICENSE", b"MIT"),
+    ]);
+
+    #[test]
+    fn embedded_files() {
+        assert!(ASSETS.file_exists("index.html"));
+        assert!(ASSETS.file_exists("/css/style.css"));
+        assert!(!ASSETS.file_exists("css"));
+        assert_eq!(ASSETS.mime_type("index.html").unwrap(), "text/html");
+        assert_eq!(ASSETS.mime_type("css/style.css").unwrap(), "text/css");
+        assert_eq!(ASSETS.mime_type("LICENSE").unwrap(), "text/plain");
+        assert_eq!(ASSETS.mime_type("README"), None);
+
+        let mut contents = String::new();
+        ASSETS
+            .open("css/style.css")
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "p { color: red }");
+    }
+}
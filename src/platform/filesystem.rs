@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::os::raw::c_char;
+use std::sync::Mutex;
+
+use ultralight_sys::{ulPlatformSetFileSystem, ulStringAssignString, ULFileHandle, ULFileSystem};
+
+use crate::platform::mime;
+use crate::ULString;
+
+/// Handle value reported to Ultralight when a file can't be opened.
+const INVALID_FILE_HANDLE: ULFileHandle = -1 as _;
+
+static FILESYSTEM: Mutex<Option<Box<dyn Filesystem>>> = Mutex::new(None);
+static FILES: Mutex<FileTable> = Mutex::new(FileTable {
+    next: 0,
+    files: BTreeMap::new(),
+});
+
+/// An opened file, as returned by [Filesystem::open].
+pub trait File: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> File for T {}
+
+/// A source of files for file:/// URLs.
+///
+/// Paths are relative to the root of the filesystem, as requested by Ultralight
+/// (`file:///css/style.css` asks for `css/style.css`).
+/// Opened files are kept by the glue, Ultralight only sees opaque handles.
+pub trait Filesystem: Send + 'static {
+    /// Check if a file exists.
+    fn file_exists(&self, path: &str) -> bool;
+
+    /// Get the MIME type of a file (eg "text/html").
+    ///
+    /// Defaults to guessing it from the extension, or from the first bytes of the file
+    /// with [mime::sniff] if the extension is unknown.
+    fn mime_type(&self, path: &str) -> Option<String> {
+        let mime = mime::guess_file(path, || self.open(path)).ok()?;
+        Some(mime.to_string())
+    }
+
+    /// Open a file for reading.
+    fn open(&self, path: &str) -> io::Result<Box<dyn File>>;
+}
+
+/// Opened files indexed by handle.
+struct FileTable {
+    next: ULFileHandle,
+    files: BTreeMap<ULFileHandle, Box<dyn File>>,
+}
+
+impl FileTable {
+    fn insert(&mut self, file: Box<dyn File>) -> ULFileHandle {
+        loop {
+            self.next = self.next.wrapping_add(1);
+            if self.next != INVALID_FILE_HANDLE && !self.files.contains_key(&self.next) {
+                break;
+            }
+        }
+        self.files.insert(self.next, file);
+        self.next
+    }
+}
+
+fn with_filesystem<R>(f: impl FnOnce(&dyn Filesystem) -> R) -> Option<R> {
+    FILESYSTEM.lock().unwrap().as_deref().map(f)
+}
+
+fn with_file<R>(handle: ULFileHandle, f: impl FnOnce(&mut dyn File) -> io::Result<R>) -> Option<R> {
+    let mut files = FILES.lock().unwrap();
+    files
+        .files
+        .get_mut(&handle)
+        .and_then(|file| f(file.as_mut()).ok())
+}
+
+fn open_handle(path: &str) -> ULFileHandle {
+    match with_filesystem(|fs| fs.open(path)) {
+        Some(Ok(file)) => FILES.lock().unwrap().insert(file),
+        Some(Err(e)) => {
+            log::debug!("Can't open file '{}' : {}", path, e);
+            INVALID_FILE_HANDLE
+        }
+        None => INVALID_FILE_HANDLE,
+    }
+}
+
+fn file_size(handle: ULFileHandle) -> Option<i64> {
+    with_file(handle, |file| {
+        let position = file.stream_position()?;
+        let size = file.seek(SeekFrom::End(0))?;
+        file.seek(SeekFrom::Start(position))?;
+        Ok(size as i64)
+    })
+}
+
+/// Read as much as possible into `data`, stopping at the end of the file.
+fn read_handle(handle: ULFileHandle, data: &mut [u8]) -> i64 {
+    with_file(handle, |file| {
+        let mut read = 0;
+        while read < data.len() {
+            match file.read(&mut data[read..]) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(read as i64)
+    })
+    .unwrap_or(-1)
+}
+
+fn close_handle(handle: ULFileHandle) {
+    FILES.lock().unwrap().files.remove(&handle);
+}
+
+fn path_string(path: ultralight_sys::ULString) -> String {
+    ULString::from(path).to_string_lossy()
+}
+
+unsafe extern "C" fn file_exists(path: ultralight_sys::ULString) -> bool {
+    let path = path_string(path);
+    with_filesystem(|fs| fs.file_exists(&path)).unwrap_or(false)
+}
+
+unsafe extern "C" fn get_file_size(handle: ULFileHandle, result: *mut i64) -> bool {
+    match file_size(handle) {
+        Some(size) => {
+            *result = size;
+            true
+        }
+        None => false,
+    }
+}
+
+unsafe extern "C" fn get_file_mime_type(
+    path: ultralight_sys::ULString,
+    result: ultralight_sys::ULString,
+) -> bool {
+    let path = path_string(path);
+    match with_filesystem(|fs| fs.mime_type(&path)).flatten() {
+        Some(mime) => {
+            let mime = ULString::from(mime.as_str());
+            ulStringAssignString(result, mime.raw());
+            true
+        }
+        None => false,
+    }
+}
+
+unsafe extern "C" fn open_file(
+    path: ultralight_sys::ULString,
+    open_for_writing: bool,
+) -> ULFileHandle {
+    if open_for_writing {
+        return INVALID_FILE_HANDLE;
+    }
+    open_handle(&path_string(path))
+}
+
+unsafe extern "C" fn close_file(handle: ULFileHandle) {
+    close_handle(handle);
+}
+
+unsafe extern "C" fn read_from_file(handle: ULFileHandle, data: *mut c_char, length: i64) -> i64 {
+    if data.is_null() || length <= 0 {
+        return 0;
+    }
+    read_handle(
+        handle,
+        std::slice::from_raw_parts_mut(data as *mut u8, length as usize),
+    )
+}
+
+/// Use `filesystem` to load file:/// URLs. Files can't be opened for writing.
+///
+/// Files still opened from a previous filesystem stay readable until Ultralight closes them.
+pub fn set_filesystem_impl<T: Filesystem>(filesystem: T) {
+    *FILESYSTEM.lock().unwrap() = Some(Box::new(filesystem));
+    set_filesystem(ULFileSystem {
+        file_exists: Some(file_exists),
+        get_file_size: Some(get_file_size),
+        get_file_mime_type: Some(get_file_mime_type),
+        open_file: Some(open_file),
+        close_file: Some(close_file),
+        read_from_file: Some(read_from_file),
+    });
+}
+
+/// Set a custom FileSystem implementation.
+/// This is used for loading File URLs (eg, file:///page.html).
+/// If you don't call this, and are not using ulCreateApp() or ulEnablePlatformFileSystem(),
+/// you will not be able to load any File URLs.
+pub fn set_filesystem(filesystem: ULFileSystem) {
+    unsafe {
+        ulPlatformSetFileSystem(filesystem);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    struct Memory;
+
+    impl Filesystem for Memory {
+        fn file_exists(&self, path: &str) -> bool {
+            path == "index.html"
+        }
+
+        fn mime_type(&self, _path: &str) -> Option<String> {
+            Some("text/html".to_string())
+        }
+
+        fn open(&self, path: &str) -> io::Result<Box<dyn File>> {
+            if self.file_exists(path) {
+                Ok(Box::new(Cursor::new(b"<p>Hello</p>".to_vec())))
+            } else {
+                Err(io::ErrorKind::NotFound.into())
+            }
+        }
+    }
+
+    #[test]
+    fn file_handles() {
+        *FILESYSTEM.lock().unwrap() = Some(Box::new(Memory));
+
+        assert_eq!(open_handle("missing.html"), INVALID_FILE_HANDLE);
+        let handle = open_handle("index.html");
+        assert_ne!(handle, INVALID_FILE_HANDLE);
+        assert_eq!(file_size(handle), Some(12));
+
+        let mut data = [0; 8];
+        assert_eq!(read_handle(handle, &mut data), 8);
+        assert_eq!(&data, b"<p>Hello");
+        assert_eq!(file_size(handle), Some(12));
+        assert_eq!(read_handle(handle, &mut data), 4);
+        assert_eq!(&data[..4], b"</p>");
+
+        close_handle(handle);
+        assert_eq!(file_size(handle), None);
+        assert_eq!(read_handle(handle, &mut data), -1);
+    }
+}
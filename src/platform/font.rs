@@ -0,0 +1,493 @@
+use std::fmt::Write;
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::platform::{File, Filesystem};
+use crate::Config;
+
+/// A font variant served by a [FontLoader].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontDescription {
+    pub family: String,
+    /// CSS weight, 100 to 900 (400 is normal, 700 is bold).
+    pub weight: u16,
+    pub italic: bool,
+}
+
+/// A source of fonts.
+///
+/// The C API of Ultralight 1.2 doesn't let us replace its font loader : the platform font loader
+/// (see [enable_fontloader](crate::platform::enable_fontloader)) still resolves font names.
+/// A `FontLoader` is hooked in through CSS instead, with [apply_font_loader] : every font it serves
+/// is declared with `@font-face` rules, loaded through a [Filesystem] with [FontFiles],
+/// and its fallback font becomes the default font family.
+/// Text then renders with the same fonts everywhere, as long as pages don't ask for other families.
+///
+/// Per-character font fallback is not supported, the C API has no hook for it : pages asking for
+/// other families, and characters missing from the served fonts, are still resolved by the
+/// platform font loader.
+pub trait FontLoader: Send + Sync + 'static {
+    /// All the font variants this loader can serve.
+    fn fonts(&self) -> Vec<FontDescription>;
+
+    /// Family used when a page doesn't specify one.
+    fn fallback_font(&self) -> String;
+
+    /// Family used for text in the fixed (monospace) font, defaults to the fallback font.
+    fn fallback_font_fixed(&self) -> String {
+        self.fallback_font()
+    }
+
+    /// Load the font file (TrueType or OpenType) closest to a family, weight and style.
+    fn load(&self, family: &str, weight: u16, italic: bool) -> Option<Arc<[u8]>>;
+}
+
+/// File name of a font variant without its extension. Characters other than ASCII letters and
+/// digits are escaped, so different families never share a file.
+fn font_file_stem(font: &FontDescription) -> String {
+    let mut stem = String::new();
+    for c in font.family.chars() {
+        if c.is_ascii_alphanumeric() {
+            stem.push(c);
+        } else {
+            for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+                write!(stem, "_{:02X}", byte).unwrap();
+            }
+        }
+    }
+    write!(stem, "-{}", font.weight).unwrap();
+    if font.italic {
+        stem.push_str("-italic");
+    }
+    stem
+}
+
+/// File extension and MIME type of a font file, from its sfnt version tag.
+fn font_format(data: &[u8]) -> (&'static str, &'static str) {
+    if data.starts_with(b"OTTO") {
+        ("otf", "font/otf")
+    } else {
+        ("ttf", "font/ttf")
+    }
+}
+
+/// CSS `@font-face` rules declaring all the fonts of `loader`, loaded from `url_prefix`
+/// (eg "file:///fonts/") where [FontFiles] is mounted.
+///
+/// Each font is loaded to name its file after its format, fonts that fail to load are skipped.
+pub fn font_face_stylesheet(loader: &dyn FontLoader, url_prefix: &str) -> String {
+    let mut css = String::new();
+    for font in loader.fonts() {
+        let data = match loader.load(&font.family, font.weight, font.italic) {
+            Some(data) => data,
+            None => continue,
+        };
+        writeln!(
+            css,
+            "@font-face {{ font-family: \"{}\"; font-weight: {}; font-style: {}; src: url(\"{}{}.{}\"); }}",
+            font.family.replace('"', "\\\""),
+            font.weight,
+            if font.italic { "italic" } else { "normal" },
+            url_prefix,
+            font_file_stem(&font),
+            font_format(&data).0
+        )
+        .unwrap();
+    }
+    css
+}
+
+/// Use the fonts of `loader` by default : set the default font families of `config`
+/// and return the `@font-face` rules of [font_face_stylesheet], to be set as (or added to)
+/// the user stylesheet of `config` with [Config::user_stylesheet].
+/// The returned [Filesystem] serves the font files, it must be mounted at `url_prefix`,
+/// eg with a [LayeredFilesystem](crate::platform::LayeredFilesystem).
+///
+/// Per-character font fallback is not supported by the C API, see [FontLoader].
+///
+/// ```no_run
+/// # use ultralight_rs::platform::{self, BundledFonts, LayeredFilesystem, DirectoryFilesystem};
+/// # use ultralight_rs::Config;
+/// # fn test(config: &Config) -> std::io::Result<()> {
+/// platform::enable_fontloader();
+/// let loader = BundledFonts::from_directory("fonts")?;
+/// let (fonts, css) = platform::apply_font_loader(loader, config, "file:///fonts/");
+/// config.user_stylesheet(&format!("{}body {{ margin: 0; }}", css));
+/// platform::set_filesystem_impl(
+///     LayeredFilesystem::new()
+///         .mount("/fonts/", fonts)
+///         .mount("/", DirectoryFilesystem::new("assets")),
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub fn apply_font_loader<T: FontLoader>(
+    loader: T,
+    config: &Config,
+    url_prefix: &str,
+) -> (FontFiles, String) {
+    let fallback = loader.fallback_font();
+    config.font_family_standard(&fallback);
+    config.font_family_sans_serif(&fallback);
+    config.font_family_serif(&fallback);
+    config.font_family_fixed(&loader.fallback_font_fixed());
+    let css = font_face_stylesheet(&loader, url_prefix);
+    (FontFiles::new(loader), css)
+}
+
+/// A [Filesystem] serving the font files of a [FontLoader], with the names used by
+/// [font_face_stylesheet].
+pub struct FontFiles {
+    loader: Box<dyn FontLoader>,
+}
+
+impl FontFiles {
+    pub fn new<T: FontLoader>(loader: T) -> Self {
+        FontFiles {
+            loader: Box::new(loader),
+        }
+    }
+
+    /// Data of the font file at `path`, if its extension matches the font format.
+    fn find(&self, path: &str) -> Option<Arc<[u8]>> {
+        let name = path.trim_start_matches('/');
+        self.loader.fonts().into_iter().find_map(|font| {
+            let extension = name
+                .strip_prefix(font_file_stem(&font).as_str())?
+                .strip_prefix('.')?;
+            let data = self.loader.load(&font.family, font.weight, font.italic)?;
+            (font_format(&data).0 == extension).then_some(data)
+        })
+    }
+}
+
+impl Filesystem for FontFiles {
+    fn file_exists(&self, path: &str) -> bool {
+        self.find(path).is_some()
+    }
+
+    fn mime_type(&self, path: &str) -> Option<String> {
+        let data = self.find(path)?;
+        Some(font_format(&data).1.to_string())
+    }
+
+    fn open(&self, path: &str) -> io::Result<Box<dyn File>> {
+        let data = self.find(path).ok_or(io::ErrorKind::NotFound)?;
+        Ok(Box::new(Cursor::new(data)))
+    }
+}
+
+#[derive(Clone)]
+struct BundledFont {
+    description: FontDescription,
+    data: Arc<[u8]>,
+}
+
+/// A [FontLoader] serving fonts from memory, embedded in the binary or read from a directory.
+///
+/// ```no_run
+/// # use ultralight_rs::platform::BundledFonts;
+/// # fn test() -> std::io::Result<()> {
+/// // Or include_bytes!("fonts/Inter-Regular.ttf").as_ref()
+/// let fonts = BundledFonts::new()
+///     .add_font(std::fs::read("fonts/Inter-Regular.ttf")?)?
+///     .add_font(std::fs::read("fonts/JetBrainsMono-Regular.ttf")?)?
+///     .fallback_fixed("JetBrains Mono");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct BundledFonts {
+    fonts: Vec<BundledFont>,
+    fallback: Option<String>,
+    fallback_fixed: Option<String>,
+}
+
+impl BundledFonts {
+    pub fn new() -> Self {
+        BundledFonts::default()
+    }
+
+    /// Load every TrueType and OpenType font (.ttf and .otf) of a directory.
+    pub fn from_directory<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let mut paths: Vec<_> = fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<io::Result<_>>()?;
+        paths.sort();
+        let mut fonts = BundledFonts::new();
+        for path in paths {
+            let extension = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(str::to_ascii_lowercase);
+            if matches!(extension.as_deref(), Some("ttf") | Some("otf")) {
+                let data = fs::read(&path)?;
+                fonts = fonts
+                    .add_font(data)
+                    .map_err(|e| io::Error::new(e.kind(), format!("{} : {}", path.display(), e)))?;
+            }
+        }
+        Ok(fonts)
+    }
+
+    /// Add a TrueType or OpenType font, reading its family, weight and style from the font itself.
+    pub fn add_font<D: Into<Arc<[u8]>>>(self, data: D) -> io::Result<Self> {
+        let data = data.into();
+        let description = describe_font(&data)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "unsupported font file"))?;
+        Ok(self.add(description, data))
+    }
+
+    /// Add a font with an explicit description.
+    pub fn add<D: Into<Arc<[u8]>>>(mut self, description: FontDescription, data: D) -> Self {
+        self.fonts.push(BundledFont {
+            description,
+            data: data.into(),
+        });
+        self
+    }
+
+    /// Family to use by default, the family of the first font otherwise.
+    pub fn fallback(mut self, family: &str) -> Self {
+        self.fallback = Some(family.to_string());
+        self
+    }
+
+    /// Family to use for fixed (monospace) text, the fallback font otherwise.
+    pub fn fallback_fixed(mut self, family: &str) -> Self {
+        self.fallback_fixed = Some(family.to_string());
+        self
+    }
+}
+
+impl FontLoader for BundledFonts {
+    fn fonts(&self) -> Vec<FontDescription> {
+        self.fonts.iter().map(|f| f.description.clone()).collect()
+    }
+
+    fn fallback_font(&self) -> String {
+        self.fallback
+            .clone()
+            .or_else(|| self.fonts.first().map(|f| f.description.family.clone()))
+            .unwrap_or_else(|| "sans-serif".to_string())
+    }
+
+    fn fallback_font_fixed(&self) -> String {
+        self.fallback_fixed
+            .clone()
+            .unwrap_or_else(|| self.fallback_font())
+    }
+
+    /// Closest variant of the family : same style if possible, then closest weight.
+    fn load(&self, family: &str, weight: u16, italic: bool) -> Option<Arc<[u8]>> {
+        self.fonts
+            .iter()
+            .filter(|f| f.description.family.eq_ignore_ascii_case(family))
+            .min_by_key(|f| {
+                (
+                    f.description.italic != italic,
+                    (f.description.weight as i32 - weight as i32).abs(),
+                )
+            })
+            .map(|f| f.data.clone())
+    }
+}
+
+fn be_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*data.get(at)?, *data.get(at + 1)?]))
+}
+
+fn be_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes([
+        *data.get(at)?,
+        *data.get(at + 1)?,
+        *data.get(at + 2)?,
+        *data.get(at + 3)?,
+    ]))
+}
+
+/// Find a table of a TrueType/OpenType font.
+fn sfnt_table<'a>(data: &'a [u8], tag: &[u8; 4]) -> Option<&'a [u8]> {
+    let version = be_u32(data, 0)?;
+    if version != 0x0001_0000 && &data[..4] != b"OTTO" && &data[..4] != b"true" {
+        return None;
+    }
+    (0..be_u16(data, 4)? as usize).find_map(|i| {
+        let record = 12 + i * 16;
+        if data.get(record..record + 4)? != tag {
+            return None;
+        }
+        let offset = be_u32(data, record + 8)? as usize;
+        let length = be_u32(data, record + 12)? as usize;
+        data.get(offset..offset + length)
+    })
+}
+
+/// Read a string of the `name` table, preferring english Windows (UTF-16) names.
+fn font_name(name: &[u8], name_id: u16) -> Option<String> {
+    let count = be_u16(name, 2)? as usize;
+    let storage = be_u16(name, 4)? as usize;
+    let mut best: Option<(u8, String)> = None;
+    for i in 0..count {
+        let record = 6 + i * 12;
+        if be_u16(name, record + 6)? != name_id {
+            continue;
+        }
+        let platform = be_u16(name, record)?;
+        let language = be_u16(name, record + 4)?;
+        let length = be_u16(name, record + 8)? as usize;
+        let offset = storage + be_u16(name, record + 10)? as usize;
+        let bytes = name.get(offset..offset + length)?;
+        let (rank, value) = match platform {
+            0 | 3 => {
+                let units: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect();
+                let rank = if platform == 3 && language == 0x0409 {
+                    0
+                } else {
+                    1
+                };
+                (rank, String::from_utf16_lossy(&units))
+            }
+            // Mac Roman, ASCII is enough for family names
+            1 => (2, bytes.iter().map(|&b| b as char).collect()),
+            _ => continue,
+        };
+        if best.as_ref().is_none_or(|(r, _)| rank < *r) {
+            best = Some((rank, value));
+        }
+    }
+    best.map(|(_, value)| value)
+}
+
+/// Read the family, weight and style of a TrueType/OpenType font.
+fn describe_font(data: &[u8]) -> Option<FontDescription> {
+    let name = sfnt_table(data, b"name")?;
+    // Typographic family, then family
+    let family = font_name(name, 16).or_else(|| font_name(name, 1))?;
+    let (weight, italic) = match sfnt_table(data, b"OS/2") {
+        Some(os2) => (be_u16(os2, 4)?, be_u16(os2, 62)? & (1 | 1 << 9) != 0),
+        None => {
+            let style = be_u16(sfnt_table(data, b"head")?, 44)?;
+            (if style & 1 != 0 { 700 } else { 400 }, style & 2 != 0)
+        }
+    };
+    Some(FontDescription {
+        family,
+        weight: weight.clamp(1, 1000),
+        italic,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    /// A font with only the `name` and `OS/2` tables.
+    fn font(family: &str, weight: u16, italic: bool) -> Vec<u8> {
+        let family: Vec<u8> = family
+            .encode_utf16()
+            .flat_map(|u| u.to_be_bytes())
+            .collect();
+        let mut name = Vec::new();
+        for value in [0u16, 1, 18].iter().chain(&[3, 1, 0x0409, 1]) {
+            name.extend_from_slice(&value.to_be_bytes());
+        }
+        name.extend_from_slice(&(family.len() as u16).to_be_bytes());
+        name.extend_from_slice(&[0, 0]);
+        name.extend_from_slice(&family);
+
+        let mut os2 = vec![0; 78];
+        os2[4..6].copy_from_slice(&weight.to_be_bytes());
+        os2[62..64].copy_from_slice(&(if italic { 1u16 } else { 0x40 }).to_be_bytes());
+
+        let mut data = vec![0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0];
+        let mut offset = 12 + 2 * 16;
+        for (tag, table) in [(b"OS/2", &os2), (b"name", &name)] {
+            data.extend_from_slice(tag);
+            data.extend_from_slice(&[0; 4]);
+            data.extend_from_slice(&(offset as u32).to_be_bytes());
+            data.extend_from_slice(&(table.len() as u32).to_be_bytes());
+            offset += table.len();
+        }
+        data.extend_from_slice(&os2);
+        data.extend_from_slice(&name);
+        data
+    }
+
+    #[test]
+    fn bundled_fonts() {
+        let fonts = BundledFonts::new()
+            .add_font(font("Inter", 400, false))
+            .unwrap()
+            .add_font(font("Inter", 700, false))
+            .unwrap()
+            .add_font(font("Inter", 400, true))
+            .unwrap()
+            .add_font(font("Fira Mono", 400, false))
+            .unwrap()
+            .add_font(font("Fira_Mono", 400, false))
+            .unwrap()
+            .fallback_fixed("Fira Mono");
+        assert!(fonts.clone().add_font(vec![0; 64]).is_err());
+
+        assert_eq!(
+            fonts.fonts()[2],
+            FontDescription {
+                family: "Inter".to_string(),
+                weight: 400,
+                italic: true
+            }
+        );
+        assert_eq!(fonts.fallback_font(), "Inter");
+        assert_eq!(fonts.fallback_font_fixed(), "Fira Mono");
+        assert_eq!(
+            *fonts.load("inter", 600, false).unwrap(),
+            *font("Inter", 700, false)
+        );
+        assert_eq!(
+            *fonts.load("Inter", 700, true).unwrap(),
+            *font("Inter", 400, true)
+        );
+        assert!(fonts.load("Arial", 400, false).is_none());
+
+        let css = font_face_stylesheet(&fonts, "file:///fonts/");
+        assert!(css.contains(
+            "@font-face { font-family: \"Fira Mono\"; font-weight: 400; font-style: normal; \
+             src: url(\"file:///fonts/Fira_20Mono-400.ttf\"); }"
+        ));
+        assert!(css.contains("src: url(\"file:///fonts/Fira_5FMono-400.ttf\")"));
+
+        let mut opentype = font("Inter", 400, false);
+        opentype[..4].copy_from_slice(b"OTTO");
+        let files = FontFiles::new(fonts.add(
+            FontDescription {
+                family: "Inter Display".to_string(),
+                weight: 400,
+                italic: false,
+            },
+            opentype,
+        ));
+        assert!(files.file_exists("Inter-400-italic.ttf"));
+        assert!(!files.file_exists("Inter-500.ttf"));
+        assert!(!files.file_exists("Inter_20Display-400.ttf"));
+        assert_eq!(
+            files.mime_type("Inter_20Display-400.otf").unwrap(),
+            "font/otf"
+        );
+        for (path, family) in [
+            ("Fira_20Mono-400.ttf", "Fira Mono"),
+            ("Fira_5FMono-400.ttf", "Fira_Mono"),
+        ] {
+            let mut data = Vec::new();
+            files.open(path).unwrap().read_to_end(&mut data).unwrap();
+            assert_eq!(data, font(family, 400, false));
+        }
+    }
+}
@@ -0,0 +1,504 @@
+use std::any::Any;
+use std::borrow::Cow;
+use std::mem::{align_of, size_of};
+use std::slice;
+use std::sync::Mutex;
+
+use ultralight_sys::{
+    ulPlatformSetGPUDriver, ULBitmap, ULCommand, ULCommandList, ULGPUDriver, ULGPUState,
+    ULIndexBuffer, ULIntRect, ULRenderBuffer, ULVertexBuffer, ULVertexBufferFormat,
+};
+
+use crate::Bitmap;
+
+pub use self::recording::*;
+pub use self::software::*;
+
+mod recording;
+mod software;
+
+pub type VertexBufferFormat = ULVertexBufferFormat;
+
+/// Driver set with [set_gpu_driver_impl] or [set_gpu_driver_instance].
+static GPU_DRIVER: Mutex<Option<Box<dyn AnyGpuDriver>>> = Mutex::new(None);
+
+/// Vertex layout used to fill paths.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Vertex2f4ub2f {
+    pub pos: [f32; 2],
+    pub color: [u8; 4],
+    pub obj: [f32; 2],
+}
+
+/// Vertex layout used for quads (fills, images, text, shadows...).
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Vertex2f4ub2f2f28f {
+    pub pos: [f32; 2],
+    pub color: [u8; 4],
+    pub tex: [f32; 2],
+    pub obj: [f32; 2],
+    pub data0: [f32; 4],
+    pub data1: [f32; 4],
+    pub data2: [f32; 4],
+    pub data3: [f32; 4],
+    pub data4: [f32; 4],
+    pub data5: [f32; 4],
+    pub data6: [f32; 4],
+}
+
+/// Reinterpret raw bytes as a slice of `T`, copying only if the data isn't aligned.
+///
+/// # Safety
+/// `T` must be valid for any bit pattern.
+unsafe fn cast_slice<T: Copy>(data: &[u8]) -> Cow<'_, [T]> {
+    let len = data.len() / size_of::<T>();
+    if data.as_ptr().align_offset(align_of::<T>()) == 0 {
+        Cow::Borrowed(slice::from_raw_parts(data.as_ptr() as *const T, len))
+    } else {
+        Cow::Owned(
+            (0..len)
+                .map(|i| (data.as_ptr() as *const T).add(i).read_unaligned())
+                .collect(),
+        )
+    }
+}
+
+/// Vertex data of a geometry, borrowed from the library for the duration of the call.
+#[derive(Debug, Copy, Clone)]
+pub struct VertexBuffer<'a> {
+    pub format: VertexBufferFormat,
+    pub data: &'a [u8],
+}
+
+impl<'a> VertexBuffer<'a> {
+    /// # Safety
+    /// The buffer data must be valid for reads of `size` bytes during `'a`.
+    pub unsafe fn from_raw(raw: &ULVertexBuffer) -> Self {
+        VertexBuffer {
+            format: raw.format,
+            data: raw_bytes(raw.data, raw.size),
+        }
+    }
+
+    /// The vertices, if the format is [VertexBufferFormat::kVertexBufferFormat_2f_4ub_2f].
+    pub fn vertices_2f_4ub_2f(&self) -> Option<Cow<'a, [Vertex2f4ub2f]>> {
+        match self.format {
+            VertexBufferFormat::kVertexBufferFormat_2f_4ub_2f => {
+                Some(unsafe { cast_slice(self.data) })
+            }
+            _ => None,
+        }
+    }
+
+    /// The vertices, if the format is [VertexBufferFormat::kVertexBufferFormat_2f_4ub_2f_2f_28f].
+    pub fn vertices_2f_4ub_2f_2f_28f(&self) -> Option<Cow<'a, [Vertex2f4ub2f2f28f]>> {
+        match self.format {
+            VertexBufferFormat::kVertexBufferFormat_2f_4ub_2f_2f_28f => {
+                Some(unsafe { cast_slice(self.data) })
+            }
+            _ => None,
+        }
+    }
+
+    /// Number of vertices.
+    pub fn len(&self) -> usize {
+        match self.format {
+            VertexBufferFormat::kVertexBufferFormat_2f_4ub_2f => {
+                self.data.len() / size_of::<Vertex2f4ub2f>()
+            }
+            _ => self.data.len() / size_of::<Vertex2f4ub2f2f28f>(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Vertex indices of a geometry (triangle list), borrowed from the library for the duration of the call.
+#[derive(Debug, Copy, Clone)]
+pub struct IndexBuffer<'a> {
+    pub data: &'a [u8],
+}
+
+impl<'a> IndexBuffer<'a> {
+    /// # Safety
+    /// The buffer data must be valid for reads of `size` bytes during `'a`.
+    pub unsafe fn from_raw(raw: &ULIndexBuffer) -> Self {
+        IndexBuffer {
+            data: raw_bytes(raw.data, raw.size),
+        }
+    }
+
+    pub fn indices(&self) -> Cow<'a, [u32]> {
+        unsafe { cast_slice(self.data) }
+    }
+}
+
+unsafe fn raw_bytes<'a>(data: *const u8, size: u32) -> &'a [u8] {
+    if data.is_null() || size == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(data, size as usize)
+    }
+}
+
+/// Offscreen render target, used when drawing layers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RenderBuffer {
+    /// Backing texture for this render buffer.
+    pub texture_id: u32,
+    /// Width of the texture.
+    pub width: u32,
+    /// Height of the texture.
+    pub height: u32,
+    /// Currently unused, always false.
+    pub has_stencil_buffer: bool,
+    /// Currently unused, always false.
+    pub has_depth_buffer: bool,
+}
+
+impl From<ULRenderBuffer> for RenderBuffer {
+    fn from(raw: ULRenderBuffer) -> Self {
+        RenderBuffer {
+            texture_id: raw.texture_id,
+            width: raw.width,
+            height: raw.height,
+            has_stencil_buffer: raw.has_stencil_buffer,
+            has_depth_buffer: raw.has_depth_buffer,
+        }
+    }
+}
+
+/// Shader program to use when drawing a geometry.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ShaderType {
+    /// Quads, with [Vertex2f4ub2f2f28f] vertices.
+    Fill,
+    /// Paths, with [Vertex2f4ub2f] vertices.
+    FillPath,
+}
+
+/// The state of the GPU for a given draw command.
+#[derive(Debug, Clone)]
+pub struct GpuState {
+    /// Viewport width in pixels.
+    pub viewport_width: u32,
+    /// Viewport height in pixels.
+    pub viewport_height: u32,
+    /// Transform matrix, column-major.
+    pub transform: [f32; 16],
+    /// Whether or not we should enable texturing for the current draw command.
+    pub enable_texturing: bool,
+    /// Whether or not we should enable blending for the current draw command.
+    /// If blending is disabled, any drawn pixels should overwrite existing.
+    /// Mainly used so we can modify alpha values of the RenderBuffer during scissored clears.
+    pub enable_blend: bool,
+    pub shader_type: ShaderType,
+    /// The render buffer to use for the current draw command.
+    pub render_buffer_id: u32,
+    /// The texture id to bind to slot #1. (Will be 0 if none)
+    pub texture_1_id: u32,
+    /// The texture id to bind to slot #2. (Will be 0 if none)
+    pub texture_2_id: u32,
+    /// The texture id to bind to slot #3. (Will be 0 if none)
+    pub texture_3_id: u32,
+    pub uniform_scalar: [f32; 8],
+    pub uniform_vector: [[f32; 4]; 8],
+    /// Clip matrices, at most 8.
+    pub clip: Vec<[f32; 16]>,
+    /// The scissor rect to apply, if scissor testing is enabled.
+    pub scissor_rect: Option<ULIntRect>,
+}
+
+impl From<&ULGPUState> for GpuState {
+    fn from(raw: &ULGPUState) -> Self {
+        let clip_size = (raw.clip_size as usize).min(raw.clip.len());
+        GpuState {
+            viewport_width: raw.viewport_width,
+            viewport_height: raw.viewport_height,
+            transform: raw.transform.data,
+            enable_texturing: raw.enable_texturing,
+            enable_blend: raw.enable_blend,
+            shader_type: if raw.shader_type == 0 {
+                ShaderType::Fill
+            } else {
+                ShaderType::FillPath
+            },
+            render_buffer_id: raw.render_buffer_id,
+            texture_1_id: raw.texture_1_id,
+            texture_2_id: raw.texture_2_id,
+            texture_3_id: raw.texture_3_id,
+            uniform_scalar: raw.uniform_scalar,
+            uniform_vector: raw.uniform_vector.map(|v| v.value),
+            clip: raw.clip[..clip_size].iter().map(|m| m.data).collect(),
+            scissor_rect: if raw.enable_scissor {
+                Some(raw.scissor_rect)
+            } else {
+                None
+            },
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CommandType {
+    /// Clear the render buffer of the state.
+    ClearRenderBuffer,
+    /// Draw a geometry to the render buffer of the state.
+    DrawGeometry,
+}
+
+/// A command to execute on the GPU.
+#[derive(Debug, Clone)]
+pub struct GpuCommand {
+    pub command_type: CommandType,
+    pub gpu_state: GpuState,
+    /// The geometry to use, only used for [CommandType::DrawGeometry].
+    pub geometry_id: u32,
+    /// The number of indices to draw.
+    pub indices_count: u32,
+    /// The index to start drawing at.
+    pub indices_offset: u32,
+}
+
+impl From<&ULCommand> for GpuCommand {
+    fn from(raw: &ULCommand) -> Self {
+        GpuCommand {
+            command_type: if raw.command_type == 0 {
+                CommandType::ClearRenderBuffer
+            } else {
+                CommandType::DrawGeometry
+            },
+            gpu_state: GpuState::from(&raw.gpu_state),
+            geometry_id: raw.geometry_id,
+            indices_count: raw.indices_count,
+            indices_offset: raw.indices_offset,
+        }
+    }
+}
+
+/// Interface the library uses to dispatch GPU calls when the GPU renderer is enabled.
+///
+/// The library calls the driver from [Renderer::render](crate::Renderer::render),
+/// on the thread the renderer was created on.
+/// Resources are created in synchronize blocks, drawing is described by the command list
+/// which should be executed later, when the application draws its frame.
+pub trait GpuDriver: Send + 'static {
+    /// Called before any state (e.g. CreateTexture(), UpdateTexture(), DestroyTexture(), etc.) is updated during a call to Renderer::render.
+    fn begin_synchronize(&mut self) {}
+
+    /// Called after all state has been updated during a call to Renderer::render.
+    fn end_synchronize(&mut self) {}
+
+    /// Get the next available texture ID.
+    fn next_texture_id(&mut self) -> u32;
+
+    /// Create a texture with a certain ID and optional bitmap.
+    /// If the bitmap is empty ([Bitmap::is_empty]), you should create an empty texture
+    /// that will be used as a render target.
+    fn create_texture(&mut self, texture_id: u32, bitmap: &Bitmap);
+
+    /// Update an existing non-RTT texture with new bitmap data.
+    fn update_texture(&mut self, texture_id: u32, bitmap: &Bitmap);
+
+    /// Destroy a texture.
+    fn destroy_texture(&mut self, texture_id: u32);
+
+    /// Generate the next available render buffer ID.
+    fn next_render_buffer_id(&mut self) -> u32;
+
+    /// Create a render buffer with certain ID and buffer description.
+    fn create_render_buffer(&mut self, render_buffer_id: u32, buffer: RenderBuffer);
+
+    /// Destroy a render buffer.
+    fn destroy_render_buffer(&mut self, render_buffer_id: u32);
+
+    /// Generate the next available geometry ID.
+    fn next_geometry_id(&mut self) -> u32;
+
+    /// Create geometry with certain ID and vertex/index data.
+    fn create_geometry(&mut self, geometry_id: u32, vertices: VertexBuffer, indices: IndexBuffer);
+
+    /// Update existing geometry with new vertex/index data.
+    fn update_geometry(&mut self, geometry_id: u32, vertices: VertexBuffer, indices: IndexBuffer);
+
+    /// Destroy geometry.
+    fn destroy_geometry(&mut self, geometry_id: u32);
+
+    /// Update command list (you should copy the commands to your own structure).
+    fn update_command_list(&mut self, commands: &[GpuCommand]);
+}
+
+/// Gives access to the concrete type of the driver.
+trait AnyGpuDriver: GpuDriver {
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: GpuDriver> AnyGpuDriver for T {
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Call `f` with the current driver, if any.
+fn dispatch<R: Default>(f: impl FnOnce(&mut dyn AnyGpuDriver) -> R) -> R {
+    match GPU_DRIVER.lock().unwrap().as_mut() {
+        Some(driver) => f(driver.as_mut()),
+        None => R::default(),
+    }
+}
+
+unsafe extern "C" fn begin_synchronize() {
+    dispatch(|d| d.begin_synchronize());
+}
+
+unsafe extern "C" fn end_synchronize() {
+    dispatch(|d| d.end_synchronize());
+}
+
+unsafe extern "C" fn next_texture_id() -> u32 {
+    dispatch(|d| d.next_texture_id())
+}
+
+unsafe extern "C" fn create_texture(texture_id: u32, bitmap: ULBitmap) {
+    let bitmap = Bitmap::from(bitmap);
+    dispatch(|d| d.create_texture(texture_id, &bitmap));
+}
+
+unsafe extern "C" fn update_texture(texture_id: u32, bitmap: ULBitmap) {
+    let bitmap = Bitmap::from(bitmap);
+    dispatch(|d| d.update_texture(texture_id, &bitmap));
+}
+
+unsafe extern "C" fn destroy_texture(texture_id: u32) {
+    dispatch(|d| d.destroy_texture(texture_id));
+}
+
+unsafe extern "C" fn next_render_buffer_id() -> u32 {
+    dispatch(|d| d.next_render_buffer_id())
+}
+
+unsafe extern "C" fn create_render_buffer(render_buffer_id: u32, buffer: ULRenderBuffer) {
+    dispatch(|d| d.create_render_buffer(render_buffer_id, buffer.into()));
+}
+
+unsafe extern "C" fn destroy_render_buffer(render_buffer_id: u32) {
+    dispatch(|d| d.destroy_render_buffer(render_buffer_id));
+}
+
+unsafe extern "C" fn next_geometry_id() -> u32 {
+    dispatch(|d| d.next_geometry_id())
+}
+
+unsafe extern "C" fn create_geometry(
+    geometry_id: u32,
+    vertices: ULVertexBuffer,
+    indices: ULIndexBuffer,
+) {
+    let (vertices, indices) = (
+        VertexBuffer::from_raw(&vertices),
+        IndexBuffer::from_raw(&indices),
+    );
+    dispatch(|d| d.create_geometry(geometry_id, vertices, indices));
+}
+
+unsafe extern "C" fn update_geometry(
+    geometry_id: u32,
+    vertices: ULVertexBuffer,
+    indices: ULIndexBuffer,
+) {
+    let (vertices, indices) = (
+        VertexBuffer::from_raw(&vertices),
+        IndexBuffer::from_raw(&indices),
+    );
+    dispatch(|d| d.update_geometry(geometry_id, vertices, indices));
+}
+
+unsafe extern "C" fn destroy_geometry(geometry_id: u32) {
+    dispatch(|d| d.destroy_geometry(geometry_id));
+}
+
+unsafe extern "C" fn update_command_list(list: ULCommandList) {
+    let commands: Vec<GpuCommand> = if list.commands.is_null() {
+        Vec::new()
+    } else {
+        slice::from_raw_parts(list.commands, list.size as usize)
+            .iter()
+            .map(GpuCommand::from)
+            .collect()
+    };
+    dispatch(|d| d.update_command_list(&commands));
+}
+
+/// Create a default `T` and use it as the GPU driver.
+pub fn set_gpu_driver_impl<T: GpuDriver + Default>() {
+    set_gpu_driver_instance(T::default());
+}
+
+/// Use `driver` as the GPU driver. It can then be accessed with [with_gpu_driver].
+pub fn set_gpu_driver_instance<T: GpuDriver>(driver: T) {
+    *GPU_DRIVER.lock().unwrap() = Some(Box::new(driver));
+    set_gpu_driver(ULGPUDriver {
+        begin_synchronize: Some(begin_synchronize),
+        end_synchronize: Some(end_synchronize),
+        next_texture_id: Some(next_texture_id),
+        create_texture: Some(create_texture),
+        update_texture: Some(update_texture),
+        destroy_texture: Some(destroy_texture),
+        next_render_buffer_id: Some(next_render_buffer_id),
+        create_render_buffer: Some(create_render_buffer),
+        destroy_render_buffer: Some(destroy_render_buffer),
+        next_geometry_id: Some(next_geometry_id),
+        create_geometry: Some(create_geometry),
+        update_geometry: Some(update_geometry),
+        destroy_geometry: Some(destroy_geometry),
+        update_command_list: Some(update_command_list),
+    });
+}
+
+/// Call `f` with the driver set with [set_gpu_driver_instance] or [set_gpu_driver_impl],
+/// e.g. to execute the command list when drawing a frame.
+/// Returns `None` if the current driver isn't a `T`.
+///
+/// This must not be called from the driver methods.
+pub fn with_gpu_driver<T: GpuDriver, R>(f: impl FnOnce(&mut T) -> R) -> Option<R> {
+    let mut driver = GPU_DRIVER.lock().unwrap();
+    driver
+        .as_mut()
+        .and_then(|d| d.as_any_mut().downcast_mut::<T>())
+        .map(f)
+}
+
+/// Set a custom GPUDriver implementation.
+/// This should be used if you have enabled the GPU renderer in the Config and are using ulCreateRenderer()
+/// (which does not provide its own GPUDriver implementation).
+/// The GPUDriver interface is used by the library to dispatch GPU calls to your native GPU context
+/// (eg, D3D11, Metal, OpenGL, Vulkan, etc.) There are reference implementations for this interface in the AppCore repo.
+pub fn set_gpu_driver(driver: ULGPUDriver) {
+    unsafe {
+        ulPlatformSetGPUDriver(driver);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vertex_layouts() {
+        assert_eq!(size_of::<Vertex2f4ub2f>(), 20);
+        assert_eq!(size_of::<Vertex2f4ub2f2f28f>(), 140);
+    }
+
+    #[test]
+    fn unaligned_indices() {
+        let mut bytes = vec![0u8];
+        bytes.extend_from_slice(&1u32.to_ne_bytes());
+        bytes.extend_from_slice(&2u32.to_ne_bytes());
+        let indices = IndexBuffer { data: &bytes[1..] };
+        assert_eq!(&*indices.indices(), &[1, 2]);
+    }
+}
@@ -0,0 +1,436 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::platform::{
+    CommandType, GpuCommand, GpuDriver, GpuState, IndexBuffer, RenderBuffer, ShaderType,
+    VertexBuffer, VertexBufferFormat,
+};
+use crate::{Bitmap, BitmapFormat, Image};
+
+/// A call made by the library to the driver.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GpuCall {
+    BeginSynchronize,
+    EndSynchronize,
+    CreateTexture(u32),
+    UpdateTexture(u32),
+    DestroyTexture(u32),
+    CreateRenderBuffer(u32),
+    DestroyRenderBuffer(u32),
+    CreateGeometry(u32),
+    UpdateGeometry(u32),
+    DestroyGeometry(u32),
+    /// Number of commands in the list.
+    UpdateCommandList(usize),
+}
+
+/// A texture alive in the driver.
+#[derive(Debug, Clone)]
+pub struct TextureRecord {
+    pub width: u32,
+    pub height: u32,
+    pub format: BitmapFormat,
+    /// Whether or not the texture was created without pixels, to be used by a render buffer.
+    pub render_target: bool,
+    /// Copy of the last pixels uploaded, `None` for render targets.
+    pub image: Option<Image>,
+    /// Number of times the texture was updated after its creation.
+    pub updates: u32,
+}
+
+/// A geometry alive in the driver.
+#[derive(Debug, Clone)]
+pub struct GeometryRecord {
+    pub format: VertexBufferFormat,
+    pub vertices: Vec<u8>,
+    pub indices: Vec<u32>,
+    /// Number of times the geometry was updated after its creation.
+    pub updates: u32,
+}
+
+impl GeometryRecord {
+    /// Typed access to the vertices.
+    pub fn vertex_buffer(&self) -> VertexBuffer<'_> {
+        VertexBuffer {
+            format: self.format,
+            data: &self.vertices,
+        }
+    }
+}
+
+/// A [GpuDriver] that doesn't draw anything but keeps track of every call,
+/// to inspect what the GPU renderer produces without a GPU.
+///
+/// ```no_run
+/// # use ultralight_rs::platform::{self, RecordingGpuDriver};
+/// platform::set_gpu_driver_impl::<RecordingGpuDriver>();
+/// // Create a renderer with the GPU renderer enabled, load a page, render...
+/// let json = platform::with_gpu_driver(|driver: &mut RecordingGpuDriver| driver.to_json());
+/// ```
+#[derive(Debug, Default)]
+pub struct RecordingGpuDriver {
+    /// Every call, in order.
+    pub calls: Vec<GpuCall>,
+    pub textures: BTreeMap<u32, TextureRecord>,
+    pub render_buffers: BTreeMap<u32, RenderBuffer>,
+    pub geometries: BTreeMap<u32, GeometryRecord>,
+    /// Every command list received, in order.
+    pub command_lists: Vec<Vec<GpuCommand>>,
+    next_texture_id: u32,
+    next_render_buffer_id: u32,
+    next_geometry_id: u32,
+}
+
+impl RecordingGpuDriver {
+    pub fn new() -> Self {
+        RecordingGpuDriver::default()
+    }
+
+    /// The last command list received.
+    pub fn last_command_list(&self) -> &[GpuCommand] {
+        self.command_lists.last().map_or(&[], Vec::as_slice)
+    }
+
+    /// Forget the recorded calls and command lists, keeping the live resources.
+    pub fn clear_history(&mut self) {
+        self.calls.clear();
+        self.command_lists.clear();
+    }
+
+    /// Dump the live resources, the calls and the command lists as JSON.
+    /// Pixels and vertices are summarized by their size.
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{\"textures\":[");
+        for (i, (id, texture)) in self.textures.iter().enumerate() {
+            separator(&mut out, i);
+            let _ = write!(
+                out,
+                "{{\"id\":{},\"width\":{},\"height\":{},\"format\":\"{}\",\"render_target\":{},\"updates\":{}}}",
+                id,
+                texture.width,
+                texture.height,
+                match texture.format {
+                    BitmapFormat::kBitmapFormat_A8_UNORM => "A8_UNORM",
+                    _ => "BGRA8_UNORM_SRGB",
+                },
+                texture.render_target,
+                texture.updates
+            );
+        }
+        out.push_str("],\"render_buffers\":[");
+        for (i, (id, buffer)) in self.render_buffers.iter().enumerate() {
+            separator(&mut out, i);
+            let _ = write!(
+                out,
+                "{{\"id\":{},\"texture_id\":{},\"width\":{},\"height\":{}}}",
+                id, buffer.texture_id, buffer.width, buffer.height
+            );
+        }
+        out.push_str("],\"geometries\":[");
+        for (i, (id, geometry)) in self.geometries.iter().enumerate() {
+            separator(&mut out, i);
+            let _ = write!(
+                out,
+                "{{\"id\":{},\"format\":\"{}\",\"vertices\":{},\"indices\":{},\"updates\":{}}}",
+                id,
+                match geometry.format {
+                    VertexBufferFormat::kVertexBufferFormat_2f_4ub_2f => "2f_4ub_2f",
+                    _ => "2f_4ub_2f_2f_28f",
+                },
+                geometry.vertex_buffer().len(),
+                geometry.indices.len(),
+                geometry.updates
+            );
+        }
+        out.push_str("],\"calls\":[");
+        for (i, call) in self.calls.iter().enumerate() {
+            separator(&mut out, i);
+            let (name, value) = match call {
+                GpuCall::BeginSynchronize => ("begin_synchronize", None),
+                GpuCall::EndSynchronize => ("end_synchronize", None),
+                GpuCall::CreateTexture(id) => ("create_texture", Some(*id as usize)),
+                GpuCall::UpdateTexture(id) => ("update_texture", Some(*id as usize)),
+                GpuCall::DestroyTexture(id) => ("destroy_texture", Some(*id as usize)),
+                GpuCall::CreateRenderBuffer(id) => ("create_render_buffer", Some(*id as usize)),
+                GpuCall::DestroyRenderBuffer(id) => ("destroy_render_buffer", Some(*id as usize)),
+                GpuCall::CreateGeometry(id) => ("create_geometry", Some(*id as usize)),
+                GpuCall::UpdateGeometry(id) => ("update_geometry", Some(*id as usize)),
+                GpuCall::DestroyGeometry(id) => ("destroy_geometry", Some(*id as usize)),
+                GpuCall::UpdateCommandList(len) => ("update_command_list", Some(*len)),
+            };
+            match value {
+                Some(value) => {
+                    let _ = write!(out, "{{\"{}\":{}}}", name, value);
+                }
+                None => {
+                    let _ = write!(out, "\"{}\"", name);
+                }
+            }
+        }
+        out.push_str("],\"command_lists\":[");
+        for (i, list) in self.command_lists.iter().enumerate() {
+            separator(&mut out, i);
+            out.push('[');
+            for (j, command) in list.iter().enumerate() {
+                separator(&mut out, j);
+                command_json(&mut out, command);
+            }
+            out.push(']');
+        }
+        out.push_str("]}");
+        out
+    }
+}
+
+fn separator(out: &mut String, index: usize) {
+    if index > 0 {
+        out.push(',');
+    }
+}
+
+fn floats_json(out: &mut String, values: &[f32]) {
+    out.push('[');
+    for (i, value) in values.iter().enumerate() {
+        separator(out, i);
+        if value.is_finite() {
+            let _ = write!(out, "{}", value);
+        } else {
+            out.push_str("null");
+        }
+    }
+    out.push(']');
+}
+
+fn state_json(out: &mut String, state: &GpuState) {
+    let _ = write!(
+        out,
+        "{{\"viewport\":[{},{}],\"transform\":",
+        state.viewport_width, state.viewport_height
+    );
+    floats_json(out, &state.transform);
+    let _ = write!(
+        out,
+        ",\"enable_texturing\":{},\"enable_blend\":{},\"shader\":\"{}\",\"render_buffer_id\":{},\"textures\":[{},{},{}],\"uniform_scalar\":",
+        state.enable_texturing,
+        state.enable_blend,
+        match state.shader_type {
+            ShaderType::Fill => "fill",
+            ShaderType::FillPath => "fill_path",
+        },
+        state.render_buffer_id,
+        state.texture_1_id,
+        state.texture_2_id,
+        state.texture_3_id
+    );
+    floats_json(out, &state.uniform_scalar);
+    out.push_str(",\"uniform_vector\":[");
+    for (i, vector) in state.uniform_vector.iter().enumerate() {
+        separator(out, i);
+        floats_json(out, vector);
+    }
+    out.push_str("],\"clip\":[");
+    for (i, matrix) in state.clip.iter().enumerate() {
+        separator(out, i);
+        floats_json(out, matrix);
+    }
+    out.push_str("],\"scissor_rect\":");
+    match state.scissor_rect {
+        Some(rect) => {
+            let _ = write!(
+                out,
+                "[{},{},{},{}]",
+                rect.left, rect.top, rect.right, rect.bottom
+            );
+        }
+        None => out.push_str("null"),
+    }
+    out.push('}');
+}
+
+fn command_json(out: &mut String, command: &GpuCommand) {
+    match command.command_type {
+        CommandType::ClearRenderBuffer => out.push_str("{\"type\":\"clear_render_buffer\""),
+        CommandType::DrawGeometry => {
+            let _ = write!(
+                out,
+                "{{\"type\":\"draw_geometry\",\"geometry_id\":{},\"indices_count\":{},\"indices_offset\":{}",
+                command.geometry_id, command.indices_count, command.indices_offset
+            );
+        }
+    }
+    out.push_str(",\"state\":");
+    state_json(out, &command.gpu_state);
+    out.push('}');
+}
+
+fn texture_record(bitmap: &Bitmap) -> TextureRecord {
+    let render_target = bitmap.is_empty();
+    TextureRecord {
+        width: bitmap.width(),
+        height: bitmap.height(),
+        format: bitmap.format(),
+        render_target,
+        image: if render_target {
+            None
+        } else {
+            Some(bitmap.to_image())
+        },
+        updates: 0,
+    }
+}
+
+impl GpuDriver for RecordingGpuDriver {
+    fn begin_synchronize(&mut self) {
+        self.calls.push(GpuCall::BeginSynchronize);
+    }
+
+    fn end_synchronize(&mut self) {
+        self.calls.push(GpuCall::EndSynchronize);
+    }
+
+    fn next_texture_id(&mut self) -> u32 {
+        self.next_texture_id += 1;
+        self.next_texture_id
+    }
+
+    fn create_texture(&mut self, texture_id: u32, bitmap: &Bitmap) {
+        self.calls.push(GpuCall::CreateTexture(texture_id));
+        self.textures.insert(texture_id, texture_record(bitmap));
+    }
+
+    fn update_texture(&mut self, texture_id: u32, bitmap: &Bitmap) {
+        self.calls.push(GpuCall::UpdateTexture(texture_id));
+        let updates = self.textures.get(&texture_id).map_or(0, |t| t.updates + 1);
+        self.textures.insert(
+            texture_id,
+            TextureRecord {
+                updates,
+                ..texture_record(bitmap)
+            },
+        );
+    }
+
+    fn destroy_texture(&mut self, texture_id: u32) {
+        self.calls.push(GpuCall::DestroyTexture(texture_id));
+        self.textures.remove(&texture_id);
+    }
+
+    fn next_render_buffer_id(&mut self) -> u32 {
+        self.next_render_buffer_id += 1;
+        self.next_render_buffer_id
+    }
+
+    fn create_render_buffer(&mut self, render_buffer_id: u32, buffer: RenderBuffer) {
+        self.calls
+            .push(GpuCall::CreateRenderBuffer(render_buffer_id));
+        self.render_buffers.insert(render_buffer_id, buffer);
+    }
+
+    fn destroy_render_buffer(&mut self, render_buffer_id: u32) {
+        self.calls
+            .push(GpuCall::DestroyRenderBuffer(render_buffer_id));
+        self.render_buffers.remove(&render_buffer_id);
+    }
+
+    fn next_geometry_id(&mut self) -> u32 {
+        self.next_geometry_id += 1;
+        self.next_geometry_id
+    }
+
+    fn create_geometry(&mut self, geometry_id: u32, vertices: VertexBuffer, indices: IndexBuffer) {
+        self.calls.push(GpuCall::CreateGeometry(geometry_id));
+        self.geometries.insert(
+            geometry_id,
+            GeometryRecord {
+                format: vertices.format,
+                vertices: vertices.data.to_vec(),
+                indices: indices.indices().into_owned(),
+                updates: 0,
+            },
+        );
+    }
+
+    fn update_geometry(&mut self, geometry_id: u32, vertices: VertexBuffer, indices: IndexBuffer) {
+        self.calls.push(GpuCall::UpdateGeometry(geometry_id));
+        let updates = self
+            .geometries
+            .get(&geometry_id)
+            .map_or(0, |g| g.updates + 1);
+        self.geometries.insert(
+            geometry_id,
+            GeometryRecord {
+                format: vertices.format,
+                vertices: vertices.data.to_vec(),
+                indices: indices.indices().into_owned(),
+                updates,
+            },
+        );
+    }
+
+    fn destroy_geometry(&mut self, geometry_id: u32) {
+        self.calls.push(GpuCall::DestroyGeometry(geometry_id));
+        self.geometries.remove(&geometry_id);
+    }
+
+    fn update_command_list(&mut self, commands: &[GpuCommand]) {
+        self.calls.push(GpuCall::UpdateCommandList(commands.len()));
+        self.command_lists.push(commands.to_vec());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_geometry_and_commands() {
+        let mut driver = RecordingGpuDriver::new();
+        let id = driver.next_geometry_id();
+        let vertices = [0u8; 20 * 3];
+        let indices: Vec<u8> = [0u32, 1, 2].iter().flat_map(|i| i.to_ne_bytes()).collect();
+        driver.begin_synchronize();
+        driver.create_geometry(
+            id,
+            VertexBuffer {
+                format: VertexBufferFormat::kVertexBufferFormat_2f_4ub_2f,
+                data: &vertices,
+            },
+            IndexBuffer { data: &indices },
+        );
+        driver.end_synchronize();
+        driver.update_command_list(&[GpuCommand {
+            command_type: CommandType::DrawGeometry,
+            gpu_state: GpuState {
+                viewport_width: 4,
+                viewport_height: 2,
+                transform: [0.0; 16],
+                enable_texturing: false,
+                enable_blend: true,
+                shader_type: ShaderType::FillPath,
+                render_buffer_id: 0,
+                texture_1_id: 0,
+                texture_2_id: 0,
+                texture_3_id: 0,
+                uniform_scalar: [0.0; 8],
+                uniform_vector: [[0.0; 4]; 8],
+                clip: Vec::new(),
+                scissor_rect: None,
+            },
+            geometry_id: id,
+            indices_count: 3,
+            indices_offset: 0,
+        }]);
+
+        assert_eq!(driver.geometries[&id].indices, vec![0, 1, 2]);
+        assert_eq!(driver.geometries[&id].vertex_buffer().len(), 3);
+        assert_eq!(driver.last_command_list().len(), 1);
+
+        let json = driver.to_json();
+        assert!(json.starts_with(
+            "{\"textures\":[],\"render_buffers\":[],\"geometries\":[{\"id\":1,\"format\":\"2f_4ub_2f\",\"vertices\":3,\"indices\":3,\"updates\":0}],\
+            \"calls\":[\"begin_synchronize\",{\"create_geometry\":1},\"end_synchronize\",{\"update_command_list\":1}],\
+            \"command_lists\":[[{\"type\":\"draw_geometry\",\"geometry_id\":1,\"indices_count\":3,\"indices_offset\":0,\"state\":{\"viewport\":[4,2]"
+        ));
+        assert!(json.ends_with("\"clip\":[],\"scissor_rect\":null}}]]}"));
+    }
+}
@@ -0,0 +1,547 @@
+use std::collections::HashMap;
+
+use ultralight_sys::ULRenderTarget;
+
+use crate::platform::{
+    CommandType, GpuCommand, GpuDriver, GpuState, IndexBuffer, RenderBuffer, ShaderType,
+    Vertex2f4ub2f, Vertex2f4ub2f2f28f, VertexBuffer,
+};
+use crate::{Bitmap, Image, PixelFormat};
+
+/// Fill types of the quad shader, stored in `data0[0]`.
+const FILL_TYPE_SOLID: u32 = 0;
+const FILL_TYPE_IMAGE: u32 = 1;
+const FILL_TYPE_GLYPH: u32 = 9;
+
+/// Premultiplied RGBA pixels, channels in 0..1.
+struct Texture {
+    width: u32,
+    height: u32,
+    data: Vec<[f32; 4]>,
+}
+
+impl Texture {
+    fn new(width: u32, height: u32) -> Self {
+        Texture {
+            width,
+            height,
+            data: vec![[0.0; 4]; width as usize * height as usize],
+        }
+    }
+
+    fn from_bitmap(bitmap: &Bitmap) -> Self {
+        if bitmap.is_empty() {
+            return Texture::new(bitmap.width(), bitmap.height());
+        }
+        let image = bitmap.to_image().to_format(PixelFormat::Rgba8);
+        Texture {
+            width: image.width,
+            height: image.height,
+            data: image
+                .rows()
+                .flat_map(|row| row.chunks_exact(4))
+                .map(|p| {
+                    [
+                        p[0] as f32 / 255.0,
+                        p[1] as f32 / 255.0,
+                        p[2] as f32 / 255.0,
+                        p[3] as f32 / 255.0,
+                    ]
+                })
+                .collect(),
+        }
+    }
+
+    /// Bilinear sampling with normalized coordinates, clamped to the edges.
+    fn sample(&self, u: f32, v: f32) -> [f32; 4] {
+        if self.width == 0 || self.height == 0 {
+            return [0.0; 4];
+        }
+        let x = (u * self.width as f32 - 0.5).clamp(0.0, self.width as f32 - 1.0);
+        let y = (v * self.height as f32 - 0.5).clamp(0.0, self.height as f32 - 1.0);
+        let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+        let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+        let texel = |x: u32, y: u32| self.data[(y * self.width + x) as usize];
+        let (a, b, c, d) = (texel(x0, y0), texel(x1, y0), texel(x0, y1), texel(x1, y1));
+        let mut out = [0.0; 4];
+        for i in 0..4 {
+            let top = a[i] + (b[i] - a[i]) * fx;
+            let bottom = c[i] + (d[i] - c[i]) * fx;
+            out[i] = top + (bottom - top) * fy;
+        }
+        out
+    }
+
+    fn to_image(&self, width: u32, height: u32) -> Image {
+        let (width, height) = (width.min(self.width), height.min(self.height));
+        let mut data = Vec::with_capacity(width as usize * height as usize * 4);
+        for y in 0..height {
+            let row = (y * self.width) as usize;
+            for pixel in &self.data[row..row + width as usize] {
+                data.extend(
+                    pixel
+                        .iter()
+                        .map(|c| (c * 255.0).round().clamp(0.0, 255.0) as u8),
+                );
+            }
+        }
+        Image {
+            width,
+            height,
+            stride: width * 4,
+            format: PixelFormat::Rgba8,
+            premultiplied: true,
+            data,
+        }
+    }
+}
+
+/// Vertex data kept for drawing.
+enum Geometry {
+    Path(Vec<Vertex2f4ub2f>, Vec<u32>),
+    Quad(Vec<Vertex2f4ub2f2f28f>, Vec<u32>),
+}
+
+impl Geometry {
+    fn new(vertices: &VertexBuffer, indices: &IndexBuffer) -> Self {
+        let indices = indices.indices().into_owned();
+        match vertices.vertices_2f_4ub_2f() {
+            Some(path) => Geometry::Path(path.into_owned(), indices),
+            None => Geometry::Quad(
+                vertices
+                    .vertices_2f_4ub_2f_2f_28f()
+                    .map(|v| v.into_owned())
+                    .unwrap_or_default(),
+                indices,
+            ),
+        }
+    }
+}
+
+/// A vertex in render buffer coordinates.
+#[derive(Copy, Clone)]
+struct RasterVertex {
+    x: f32,
+    y: f32,
+    /// Straight alpha color.
+    color: [f32; 4],
+    tex: [f32; 2],
+    fill_type: u32,
+}
+
+fn raster_vertex(state: &GpuState, pos: [f32; 2], color: [u8; 4]) -> RasterVertex {
+    // Column-major transform, applied to (x, y, 0, 1)
+    let m = &state.transform;
+    let (x, y) = (pos[0], pos[1]);
+    let w = m[3] * x + m[7] * y + m[15];
+    let w = if w == 0.0 { 1.0 } else { w };
+    RasterVertex {
+        x: (m[0] * x + m[4] * y + m[12]) / w,
+        y: (m[1] * x + m[5] * y + m[13]) / w,
+        color: [
+            color[0] as f32 / 255.0,
+            color[1] as f32 / 255.0,
+            color[2] as f32 / 255.0,
+            color[3] as f32 / 255.0,
+        ],
+        tex: [0.0; 2],
+        fill_type: FILL_TYPE_SOLID,
+    }
+}
+
+/// Signed area of the parallelogram (a, b, p), positive when p is on the right of a -> b (y down).
+fn edge(a: (f32, f32), b: (f32, f32), p: (f32, f32)) -> f32 {
+    (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+}
+
+/// Tie-break for pixels exactly on an edge, so pixels on an edge shared by two triangles
+/// are only drawn once (the edge is walked in opposite directions by the two triangles).
+fn owns_edge(a: (f32, f32), b: (f32, f32)) -> bool {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    dy > 0.0 || (dy == 0.0 && dx < 0.0)
+}
+
+/// Rasterize a triangle, calling `shade` with the interpolated vertex for each covered pixel.
+fn draw_triangle(
+    target: &mut Texture,
+    bounds: (u32, u32, u32, u32),
+    vertices: [RasterVertex; 3],
+    blend: bool,
+    shade: impl Fn(&RasterVertex) -> [f32; 4],
+) {
+    let [mut v0, mut v1, v2] = vertices;
+    let mut area = edge((v0.x, v0.y), (v1.x, v1.y), (v2.x, v2.y));
+    if area == 0.0 || !area.is_finite() {
+        return;
+    }
+    if area < 0.0 {
+        std::mem::swap(&mut v0, &mut v1);
+        area = -area;
+    }
+    let (p0, p1, p2) = ((v0.x, v0.y), (v1.x, v1.y), (v2.x, v2.y));
+
+    let min_x = p0.0.min(p1.0).min(p2.0).floor().max(bounds.0 as f32) as u32;
+    let min_y = p0.1.min(p1.1).min(p2.1).floor().max(bounds.1 as f32) as u32;
+    let max_x = p0.0.max(p1.0).max(p2.0).ceil().min(bounds.2 as f32) as u32;
+    let max_y = p0.1.max(p1.1).max(p2.1).ceil().min(bounds.3 as f32) as u32;
+
+    let inside = |w: f32, a: (f32, f32), b: (f32, f32)| w > 0.0 || (w == 0.0 && owns_edge(a, b));
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let p = (x as f32 + 0.5, y as f32 + 0.5);
+            let (w0, w1, w2) = (edge(p1, p2, p), edge(p2, p0, p), edge(p0, p1, p));
+            if !(inside(w0, p1, p2) && inside(w1, p2, p0) && inside(w2, p0, p1)) {
+                continue;
+            }
+            let (l0, l1, l2) = (w0 / area, w1 / area, w2 / area);
+            let lerp = |a: f32, b: f32, c: f32| a * l0 + b * l1 + c * l2;
+            let mut fragment = v0;
+            for i in 0..4 {
+                fragment.color[i] = lerp(v0.color[i], v1.color[i], v2.color[i]);
+            }
+            for i in 0..2 {
+                fragment.tex[i] = lerp(v0.tex[i], v1.tex[i], v2.tex[i]);
+            }
+
+            let src = shade(&fragment);
+            let dst = &mut target.data[(y * target.width + x) as usize];
+            if blend {
+                for i in 0..4 {
+                    dst[i] = src[i] + dst[i] * (1.0 - src[3]);
+                }
+            } else {
+                *dst = src;
+            }
+        }
+    }
+}
+
+fn premultiplied(color: [f32; 4]) -> [f32; 4] {
+    [
+        color[0] * color[3],
+        color[1] * color[3],
+        color[2] * color[3],
+        color[3],
+    ]
+}
+
+/// A [GpuDriver] rasterizing the command lists on the CPU, to run the GPU renderer without a GPU.
+///
+/// This is a reference implementation, it's slow and only supports part of the shaders :
+/// solid, image and glyph quads and path fills are drawn, with blending and scissor rects.
+/// Other quad fill types (gradients, rounded rects, box shadows) are drawn with their solid vertex color,
+/// and clip masks are ignored.
+///
+/// Command lists are executed as soon as they are received, the render targets are up to date
+/// after each [Renderer::render](crate::Renderer::render).
+///
+/// ```no_run
+/// # use ultralight_rs::platform::{self, SoftwareGpuDriver};
+/// # use ultralight_rs::View;
+/// # fn test(view: &View) {
+/// platform::set_gpu_driver_impl::<SoftwareGpuDriver>();
+/// // Create a renderer with the GPU renderer enabled, load a page, render...
+/// let target = view.render_target();
+/// let image = platform::with_gpu_driver(|driver: &mut SoftwareGpuDriver| {
+///     driver.render_target_image(&target)
+/// });
+/// # }
+/// ```
+#[derive(Default)]
+pub struct SoftwareGpuDriver {
+    textures: HashMap<u32, Texture>,
+    render_buffers: HashMap<u32, RenderBuffer>,
+    geometries: HashMap<u32, Geometry>,
+    next_texture_id: u32,
+    next_render_buffer_id: u32,
+    next_geometry_id: u32,
+}
+
+impl SoftwareGpuDriver {
+    pub fn new() -> Self {
+        SoftwareGpuDriver::default()
+    }
+
+    /// Copy the pixels of a texture, premultiplied RGBA.
+    pub fn texture_image(&self, texture_id: u32) -> Option<Image> {
+        self.textures
+            .get(&texture_id)
+            .map(|t| t.to_image(t.width, t.height))
+    }
+
+    /// Copy the pixels of a view render target, premultiplied RGBA.
+    /// The texture can be larger than the view, only the view area is copied.
+    pub fn render_target_image(&self, target: &ULRenderTarget) -> Option<Image> {
+        if target.is_empty {
+            return None;
+        }
+        self.textures
+            .get(&target.texture_id)
+            .map(|t| t.to_image(target.width, target.height))
+    }
+
+    fn execute(&mut self, command: &GpuCommand) {
+        let state = &command.gpu_state;
+        let texture_id = match self.render_buffers.get(&state.render_buffer_id) {
+            Some(buffer) => buffer.texture_id,
+            None => return,
+        };
+        let mut target = match self.textures.remove(&texture_id) {
+            Some(target) => target,
+            None => return,
+        };
+
+        let mut bounds = (
+            0,
+            0,
+            target.width.min(state.viewport_width),
+            target.height.min(state.viewport_height),
+        );
+        if let Some(rect) = state.scissor_rect {
+            bounds = (
+                (rect.left.max(0) as u32).max(bounds.0),
+                (rect.top.max(0) as u32).max(bounds.1),
+                (rect.right.max(0) as u32).min(bounds.2),
+                (rect.bottom.max(0) as u32).min(bounds.3),
+            );
+        }
+
+        match command.command_type {
+            CommandType::ClearRenderBuffer => {
+                for y in bounds.1..bounds.3 {
+                    for x in bounds.0..bounds.2 {
+                        target.data[(y * target.width + x) as usize] = [0.0; 4];
+                    }
+                }
+            }
+            CommandType::DrawGeometry => self.draw(&mut target, bounds, command),
+        }
+        self.textures.insert(texture_id, target);
+    }
+
+    fn draw(&self, target: &mut Texture, bounds: (u32, u32, u32, u32), command: &GpuCommand) {
+        let state = &command.gpu_state;
+        let (vertices, indices): (Vec<RasterVertex>, &[u32]) =
+            match self.geometries.get(&command.geometry_id) {
+                Some(Geometry::Path(vertices, indices)) => (
+                    vertices
+                        .iter()
+                        .map(|v| raster_vertex(state, v.pos, v.color))
+                        .collect(),
+                    indices,
+                ),
+                Some(Geometry::Quad(vertices, indices)) => (
+                    vertices
+                        .iter()
+                        .map(|v| RasterVertex {
+                            tex: v.tex,
+                            fill_type: if state.shader_type == ShaderType::Fill {
+                                (v.data0[0] + 0.5) as u32
+                            } else {
+                                FILL_TYPE_SOLID
+                            },
+                            ..raster_vertex(state, v.pos, v.color)
+                        })
+                        .collect(),
+                    indices,
+                ),
+                None => return,
+            };
+        let texture = if state.enable_texturing {
+            self.textures.get(&state.texture_1_id)
+        } else {
+            None
+        };
+        let shade = |fragment: &RasterVertex| -> [f32; 4] {
+            let color = premultiplied(fragment.color);
+            match (fragment.fill_type, texture) {
+                (FILL_TYPE_IMAGE, Some(texture)) => {
+                    let texel = texture.sample(fragment.tex[0], fragment.tex[1]);
+                    [
+                        texel[0] * color[0],
+                        texel[1] * color[1],
+                        texel[2] * color[2],
+                        texel[3] * color[3],
+                    ]
+                }
+                (FILL_TYPE_GLYPH, Some(texture)) => {
+                    let coverage = texture.sample(fragment.tex[0], fragment.tex[1])[3];
+                    color.map(|c| c * coverage)
+                }
+                _ => color,
+            }
+        };
+
+        let start = command.indices_offset as usize;
+        let end = (start + command.indices_count as usize).min(indices.len());
+        for triangle in indices.get(start..end).unwrap_or(&[]).chunks_exact(3) {
+            let vertex = |i: u32| vertices.get(i as usize).copied();
+            if let (Some(a), Some(b), Some(c)) = (
+                vertex(triangle[0]),
+                vertex(triangle[1]),
+                vertex(triangle[2]),
+            ) {
+                draw_triangle(target, bounds, [a, b, c], state.enable_blend, shade);
+            }
+        }
+    }
+}
+
+impl GpuDriver for SoftwareGpuDriver {
+    fn next_texture_id(&mut self) -> u32 {
+        self.next_texture_id += 1;
+        self.next_texture_id
+    }
+
+    fn create_texture(&mut self, texture_id: u32, bitmap: &Bitmap) {
+        self.textures
+            .insert(texture_id, Texture::from_bitmap(bitmap));
+    }
+
+    fn update_texture(&mut self, texture_id: u32, bitmap: &Bitmap) {
+        self.textures
+            .insert(texture_id, Texture::from_bitmap(bitmap));
+    }
+
+    fn destroy_texture(&mut self, texture_id: u32) {
+        self.textures.remove(&texture_id);
+    }
+
+    fn next_render_buffer_id(&mut self) -> u32 {
+        self.next_render_buffer_id += 1;
+        self.next_render_buffer_id
+    }
+
+    fn create_render_buffer(&mut self, render_buffer_id: u32, buffer: RenderBuffer) {
+        // Make sure the backing texture can hold the whole buffer
+        let texture = self.textures.get(&buffer.texture_id);
+        if texture.is_none_or(|t| t.width < buffer.width || t.height < buffer.height) {
+            self.textures
+                .insert(buffer.texture_id, Texture::new(buffer.width, buffer.height));
+        }
+        self.render_buffers.insert(render_buffer_id, buffer);
+    }
+
+    fn destroy_render_buffer(&mut self, render_buffer_id: u32) {
+        self.render_buffers.remove(&render_buffer_id);
+    }
+
+    fn next_geometry_id(&mut self) -> u32 {
+        self.next_geometry_id += 1;
+        self.next_geometry_id
+    }
+
+    fn create_geometry(&mut self, geometry_id: u32, vertices: VertexBuffer, indices: IndexBuffer) {
+        self.geometries
+            .insert(geometry_id, Geometry::new(&vertices, &indices));
+    }
+
+    fn update_geometry(&mut self, geometry_id: u32, vertices: VertexBuffer, indices: IndexBuffer) {
+        self.geometries
+            .insert(geometry_id, Geometry::new(&vertices, &indices));
+    }
+
+    fn destroy_geometry(&mut self, geometry_id: u32) {
+        self.geometries.remove(&geometry_id);
+    }
+
+    fn update_command_list(&mut self, commands: &[GpuCommand]) {
+        for command in commands {
+            self.execute(command);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::VertexBufferFormat;
+
+    fn identity() -> [f32; 16] {
+        let mut m = [0.0; 16];
+        m[0] = 1.0;
+        m[5] = 1.0;
+        m[10] = 1.0;
+        m[15] = 1.0;
+        m
+    }
+
+    fn command(command_type: CommandType, indices_count: u32) -> GpuCommand {
+        GpuCommand {
+            command_type,
+            gpu_state: GpuState {
+                viewport_width: 4,
+                viewport_height: 4,
+                transform: identity(),
+                enable_texturing: false,
+                enable_blend: true,
+                shader_type: ShaderType::FillPath,
+                render_buffer_id: 1,
+                texture_1_id: 0,
+                texture_2_id: 0,
+                texture_3_id: 0,
+                uniform_scalar: [0.0; 8],
+                uniform_vector: [[0.0; 4]; 8],
+                clip: Vec::new(),
+                scissor_rect: None,
+            },
+            geometry_id: 1,
+            indices_count,
+            indices_offset: 0,
+        }
+    }
+
+    #[test]
+    fn draw_quad() {
+        let mut driver = SoftwareGpuDriver::new();
+        driver.create_render_buffer(
+            1,
+            RenderBuffer {
+                texture_id: 7,
+                width: 4,
+                height: 4,
+                has_stencil_buffer: false,
+                has_depth_buffer: false,
+            },
+        );
+
+        // A half transparent red quad covering the left half, as two triangles
+        let corners = [[0.0, 0.0], [2.0, 0.0], [2.0, 4.0], [0.0, 4.0]];
+        let vertices: Vec<u8> = corners
+            .iter()
+            .flat_map(|pos: &[f32; 2]| {
+                let mut bytes = Vec::new();
+                bytes.extend_from_slice(&pos[0].to_ne_bytes());
+                bytes.extend_from_slice(&pos[1].to_ne_bytes());
+                bytes.extend_from_slice(&[255, 0, 0, 128]);
+                bytes.extend_from_slice(&[0; 8]);
+                bytes
+            })
+            .collect();
+        let indices: Vec<u8> = [0u32, 1, 2, 0, 2, 3]
+            .iter()
+            .flat_map(|i| i.to_ne_bytes())
+            .collect();
+        driver.create_geometry(
+            1,
+            VertexBuffer {
+                format: VertexBufferFormat::kVertexBufferFormat_2f_4ub_2f,
+                data: &vertices,
+            },
+            IndexBuffer { data: &indices },
+        );
+        driver.update_command_list(&[
+            command(CommandType::ClearRenderBuffer, 0),
+            command(CommandType::DrawGeometry, 6),
+        ]);
+
+        let image = driver.texture_image(7).unwrap();
+        // Pixels on the diagonal shared by the triangles are only blended once
+        for y in 0..4 {
+            assert_eq!(image.pixel(0, y), [128, 0, 0, 128]);
+            assert_eq!(image.pixel(1, y), [128, 0, 0, 128]);
+            assert_eq!(image.pixel(2, y), [0, 0, 0, 0]);
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::platform::{File, Filesystem};
+
+/// A [Filesystem] over a directory on disk, like the one enabled by
+/// [enable_default_filesystem](crate::platform::enable_default_filesystem) but usable as a layer
+/// of a [LayeredFilesystem].
+///
+/// Absolute paths and paths with `..` components are rejected.
+#[derive(Debug, Clone)]
+pub struct DirectoryFilesystem {
+    root: PathBuf,
+}
+
+impl DirectoryFilesystem {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        DirectoryFilesystem { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Path of a file on disk, if it stays under the root.
+    pub fn resolve(&self, path: &str) -> Option<PathBuf> {
+        let path = Path::new(path.trim_start_matches('/'));
+        if path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            Some(self.root.join(path))
+        } else {
+            None
+        }
+    }
+}
+
+impl Filesystem for DirectoryFilesystem {
+    fn file_exists(&self, path: &str) -> bool {
+        self.resolve(path).is_some_and(|p| p.is_file())
+    }
+
+    fn open(&self, path: &str) -> io::Result<Box<dyn File>> {
+        let path = self.resolve(path).ok_or(io::ErrorKind::PermissionDenied)?;
+        Ok(Box::new(fs::File::open(path)?))
+    }
+}
+
+struct Mount {
+    /// Normalized prefix, without leading `/` and with a trailing `/` (empty for the root).
+    prefix: String,
+    priority: i32,
+    filesystem: Box<dyn Filesystem>,
+}
+
+/// A [Filesystem] composed of other filesystems mounted at path prefixes.
+///
+/// A path is looked up in every layer whose prefix matches it, by decreasing priority
+/// (longest prefix first for equal priorities), until a layer has the file.
+/// The prefix is removed from the path given to the layer.
+/// Paths that don't match any prefix are denied.
+///
+/// ```no_run
+/// # use ultralight_rs::platform::{self, DirectoryFilesystem, EmbeddedFilesystem, LayeredFilesystem};
+/// # static UI_ASSETS: EmbeddedFilesystem = EmbeddedFilesystem::new(&[]);
+/// // UI_ASSETS is built with ultralight_rs::include_assets!("ui/")
+/// platform::set_filesystem_impl(
+///     LayeredFilesystem::new()
+///         .mount("/inspector/", DirectoryFilesystem::new("assets/inspector"))
+///         // Prefer files on disk, falling back to the embedded ones
+///         .mount_with_priority("/app/", 1, DirectoryFilesystem::new("ui"))
+///         .mount("/app/", UI_ASSETS),
+/// );
+/// ```
+#[derive(Default)]
+pub struct LayeredFilesystem {
+    mounts: Vec<Mount>,
+}
+
+impl LayeredFilesystem {
+    pub fn new() -> Self {
+        LayeredFilesystem::default()
+    }
+
+    /// Mount a filesystem at `prefix` (eg `/app/`, or `/` for every path) with priority 0.
+    pub fn mount<F: Filesystem>(self, prefix: &str, filesystem: F) -> Self {
+        self.mount_with_priority(prefix, 0, filesystem)
+    }
+
+    /// Mount a filesystem at `prefix`, layers with a higher priority are looked up first.
+    pub fn mount_with_priority<F: Filesystem>(
+        mut self,
+        prefix: &str,
+        priority: i32,
+        filesystem: F,
+    ) -> Self {
+        let mut prefix = prefix.trim_matches('/').to_string();
+        if !prefix.is_empty() {
+            prefix.push('/');
+        }
+        self.mounts.push(Mount {
+            prefix,
+            priority,
+            filesystem: Box::new(filesystem),
+        });
+        // Stable sort, layers mounted first win ties
+        self.mounts.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then(b.prefix.len().cmp(&a.prefix.len()))
+        });
+        self
+    }
+
+    /// Layers matching `path`, in lookup order, with the path relative to the layer.
+    fn layers<'a>(&'a self, path: &'a str) -> impl Iterator<Item = (&'a dyn Filesystem, &'a str)> {
+        let path = path.trim_start_matches('/');
+        self.mounts.iter().filter_map(move |mount| {
+            path.strip_prefix(mount.prefix.as_str())
+                .map(|rest| (mount.filesystem.as_ref(), rest))
+        })
+    }
+
+    /// The layer serving `path`.
+    fn find<'a>(&'a self, path: &'a str) -> Option<(&'a dyn Filesystem, &'a str)> {
+        self.layers(path).find(|(fs, path)| fs.file_exists(path))
+    }
+}
+
+impl Filesystem for LayeredFilesystem {
+    fn file_exists(&self, path: &str) -> bool {
+        self.find(path).is_some()
+    }
+
+    fn mime_type(&self, path: &str) -> Option<String> {
+        self.find(path).and_then(|(fs, path)| fs.mime_type(path))
+    }
+
+    fn open(&self, path: &str) -> io::Result<Box<dyn File>> {
+        match self.find(path) {
+            Some((fs, path)) => fs.open(path),
+            None if self.layers(path).next().is_some() => Err(io::ErrorKind::NotFound.into()),
+            None => Err(io::ErrorKind::PermissionDenied.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+    use crate::platform::EmbeddedFilesystem;
+
+    static DISK: EmbeddedFilesystem = EmbeddedFilesystem::new(&[("index.html", b"disk")]);
+    static EMBEDDED: EmbeddedFilesystem =
+        EmbeddedFilesystem::new(&[("index.html", b"embedded"), ("style.css", b"embedded")]);
+    static INSPECTOR: EmbeddedFilesystem = EmbeddedFilesystem::new(&[("main.js", b"inspector")]);
+
+    fn read(fs: &LayeredFilesystem, path: &str) -> io::Result<String> {
+        let mut contents = String::new();
+        fs.open(path)?.read_to_string(&mut contents)?;
+        Ok(contents)
+    }
+
+    #[test]
+    fn layers() {
+        let fs = LayeredFilesystem::new()
+            .mount("/app/", EMBEDDED)
+            .mount_with_priority("app", 1, DISK)
+            .mount("/inspector/", INSPECTOR);
+
+        assert_eq!(read(&fs, "app/index.html").unwrap(), "disk");
+        assert_eq!(read(&fs, "/app/style.css").unwrap(), "embedded");
+        assert_eq!(read(&fs, "inspector/main.js").unwrap(), "inspector");
+        assert_eq!(fs.mime_type("app/style.css").unwrap(), "text/css");
+
+        let missing = read(&fs, "app/missing.html").unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+        let denied = read(&fs, "index.html").unwrap_err();
+        assert_eq!(denied.kind(), io::ErrorKind::PermissionDenied);
+        assert!(!fs.file_exists("appendix.html"));
+    }
+
+    #[test]
+    fn directory_traversal() {
+        let fs = DirectoryFilesystem::new("assets");
+        assert_eq!(
+            fs.resolve("/css/style.css"),
+            Some(PathBuf::from("assets/css/style.css"))
+        );
+        assert_eq!(fs.resolve("css/../../secret"), None);
+    }
+}
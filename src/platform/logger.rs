@@ -0,0 +1,334 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::os::raw::{c_uint, c_void};
+use std::sync::{Arc, Mutex};
+
+use log::{Level, LevelFilter};
+use ultralight_sys::{
+    ulPlatformSetLogger, ULLogLevel, ULLogger, ULMessageLevel, ULMessageSource, ULView,
+};
+
+use crate::internal::{console_level, console_source};
+use crate::ULString;
+
+static LOGGER: Mutex<Option<Box<dyn Logger>>> = Mutex::new(None);
+/// Kept apart from the logger so it can be set before it.
+static LOG_LEVEL: Mutex<LevelFilter> = Mutex::new(LevelFilter::Trace);
+/// Tags of the views forwarding their console messages, by raw view.
+static VIEW_TAGS: Mutex<BTreeMap<usize, String>> = Mutex::new(BTreeMap::new());
+
+/// Where a [LogMessage] comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogOrigin {
+    /// The library itself.
+    Platform,
+    /// The console of a view.
+    Console(ConsoleOrigin),
+}
+
+/// Location of a console message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleOrigin {
+    /// Tag given to [View::forward_console_messages](crate::View::forward_console_messages).
+    pub view: String,
+    /// Kind of source, eg "consoleapi", "js" or "network".
+    pub source: &'static str,
+    /// URL of the script.
+    pub source_id: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+/// A message from the library or from the console of a view.
+///
+/// Library levels are Error, Warn and Info, console levels use Debug for `console.debug`
+/// and Trace for `console.log`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogMessage {
+    pub level: Level,
+    pub message: String,
+    pub origin: LogOrigin,
+}
+
+impl LogMessage {
+    /// Tag of the view that logged this message, if it comes from a console.
+    pub fn view(&self) -> Option<&str> {
+        match &self.origin {
+            LogOrigin::Platform => None,
+            LogOrigin::Console(console) => Some(&console.view),
+        }
+    }
+}
+
+impl fmt::Display for LogMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.origin {
+            LogOrigin::Platform => write!(f, "{}", self.message),
+            LogOrigin::Console(c) => write!(
+                f,
+                "[{}] ({}, {}, {}) {}",
+                c.view, c.source_id, c.line, c.column, self.message
+            ),
+        }
+    }
+}
+
+/// Receives the messages of the library and of the views forwarding their console.
+/// Implemented for closures.
+pub trait Logger: Send + 'static {
+    fn log(&self, message: &LogMessage);
+}
+
+impl<F: Fn(&LogMessage) + Send + 'static> Logger for F {
+    fn log(&self, message: &LogMessage) {
+        self(message)
+    }
+}
+
+fn dispatch(message: LogMessage) {
+    if message.level > *LOG_LEVEL.lock().unwrap() {
+        return;
+    }
+    if let Some(logger) = LOGGER.lock().unwrap().as_ref() {
+        logger.log(&message);
+    }
+}
+
+unsafe extern "C" fn log_message(level: ULLogLevel, message: ultralight_sys::ULString) {
+    let level = match level {
+        ULLogLevel::kLogLevel_Error => Level::Error,
+        ULLogLevel::kLogLevel_Warning => Level::Warn,
+        ULLogLevel::kLogLevel_Info => Level::Info,
+    };
+    dispatch(LogMessage {
+        level,
+        message: ULString::from(message).to_string_lossy(),
+        origin: LogOrigin::Platform,
+    });
+}
+
+pub(crate) unsafe extern "C" fn console_message_cb(
+    _user_data: *mut c_void,
+    caller: ULView,
+    source: ULMessageSource,
+    level: ULMessageLevel,
+    message: ultralight_sys::ULString,
+    line_number: c_uint,
+    column_number: c_uint,
+    source_id: ultralight_sys::ULString,
+) {
+    let view = VIEW_TAGS
+        .lock()
+        .unwrap()
+        .get(&(caller as usize))
+        .cloned()
+        .unwrap_or_default();
+    dispatch(LogMessage {
+        level: console_level(level),
+        message: ULString::from(message).to_string_lossy(),
+        origin: LogOrigin::Console(ConsoleOrigin {
+            view,
+            source: console_source(source),
+            source_id: ULString::from(source_id).to_string_lossy(),
+            line: line_number,
+            column: column_number,
+        }),
+    });
+}
+
+pub(crate) fn set_view_tag(view: ULView, tag: &str) {
+    VIEW_TAGS
+        .lock()
+        .unwrap()
+        .insert(view as usize, tag.to_string());
+}
+
+pub(crate) fn remove_view_tag(view: ULView) {
+    VIEW_TAGS.lock().unwrap().remove(&(view as usize));
+}
+
+/// Use `logger` for the messages of the library, and of the views forwarding their console
+/// with [View::forward_console_messages](crate::View::forward_console_messages).
+/// All levels are logged unless [set_log_level] is called, before or after this.
+///
+/// ```no_run
+/// # use ultralight_rs::platform;
+/// platform::set_logger_impl(|message: &platform::LogMessage| eprintln!("{}", message));
+/// platform::set_log_level(log::LevelFilter::Warn);
+/// ```
+pub fn set_logger_impl<T: Logger>(logger: T) {
+    *LOGGER.lock().unwrap() = Some(Box::new(logger));
+    set_logger(ULLogger {
+        log_message: Some(log_message),
+    });
+}
+
+/// Only give messages up to `level` to the logger set with [set_logger_impl].
+pub fn set_log_level(level: LevelFilter) {
+    *LOG_LEVEL.lock().unwrap() = level;
+}
+
+/// Set a custom Logger implementation.
+/// This is used to log debug messages to the console or to a log file.
+pub fn set_logger(logger: ULLogger) {
+    unsafe {
+        ulPlatformSetLogger(logger);
+    }
+}
+
+/// A [Logger] forwarding messages to the `log` crate.
+///
+/// Library messages use the target given to [LogForwarder::new], console messages use
+/// `<target>::<view tag>`.
+#[derive(Debug, Clone)]
+pub struct LogForwarder {
+    target: String,
+}
+
+impl LogForwarder {
+    pub fn new(target: &str) -> Self {
+        LogForwarder {
+            target: target.to_string(),
+        }
+    }
+}
+
+impl Default for LogForwarder {
+    fn default() -> Self {
+        LogForwarder::new("Ultralight")
+    }
+}
+
+impl Logger for LogForwarder {
+    fn log(&self, message: &LogMessage) {
+        match &message.origin {
+            LogOrigin::Platform => {
+                log::log!(target: &self.target, message.level, "{}", message.message)
+            }
+            LogOrigin::Console(c) => log::log!(
+                target: &format!("{}::{}", self.target, c.view),
+                message.level,
+                "({}, {}, {}) {}",
+                c.source_id,
+                c.line,
+                c.column,
+                message.message
+            ),
+        }
+    }
+}
+
+/// Enable a default logger implementation based on the `log` crate, with the "Ultralight" target.
+/// See [LogForwarder] to use another target.
+pub fn enable_default_logger() {
+    set_logger_impl(LogForwarder::default());
+}
+
+/// A [Logger] keeping the messages in memory, to check them in tests.
+///
+/// Clones share the same messages :
+/// ```no_run
+/// # use ultralight_rs::platform::{self, CapturingLogger};
+/// let logs = CapturingLogger::new();
+/// platform::set_logger_impl(logs.clone());
+/// // Load a page with view.forward_console_messages("main")...
+/// assert!(logs.messages().iter().all(|m| m.level > log::Level::Warn));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CapturingLogger {
+    messages: Arc<Mutex<Vec<LogMessage>>>,
+}
+
+impl CapturingLogger {
+    pub fn new() -> Self {
+        CapturingLogger::default()
+    }
+
+    /// Copy of the captured messages, oldest first.
+    pub fn messages(&self) -> Vec<LogMessage> {
+        self.messages.lock().unwrap().clone()
+    }
+
+    /// Take the captured messages, emptying the buffer.
+    pub fn take(&self) -> Vec<LogMessage> {
+        std::mem::take(&mut *self.messages.lock().unwrap())
+    }
+
+    /// Captured messages from the console of a view.
+    pub fn view_messages(&self, view: &str) -> Vec<LogMessage> {
+        self.messages
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| m.view() == Some(view))
+            .cloned()
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        self.messages.lock().unwrap().clear();
+    }
+}
+
+impl Logger for CapturingLogger {
+    fn log(&self, message: &LogMessage) {
+        self.messages.lock().unwrap().push(message.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_and_filter() {
+        // The level is kept when set before the logger
+        *LOGGER.lock().unwrap() = None;
+        set_log_level(LevelFilter::Warn);
+        let logs = CapturingLogger::new();
+        *LOGGER.lock().unwrap() = Some(Box::new(logs.clone()));
+        dispatch(LogMessage {
+            level: Level::Info,
+            message: "filtered".to_string(),
+            origin: LogOrigin::Platform,
+        });
+        assert!(logs.messages().is_empty());
+        set_log_level(LevelFilter::Trace);
+
+        let console = |view: &str, level: Level, message: &str| LogMessage {
+            level,
+            message: message.to_string(),
+            origin: LogOrigin::Console(ConsoleOrigin {
+                view: view.to_string(),
+                source: "consoleapi",
+                source_id: "file:///index.html".to_string(),
+                line: 3,
+                column: 9,
+            }),
+        };
+        dispatch(LogMessage {
+            level: Level::Info,
+            message: "Renderer created".to_string(),
+            origin: LogOrigin::Platform,
+        });
+        dispatch(console("main", Level::Trace, "hello"));
+        dispatch(console("popup", Level::Error, "oops"));
+
+        assert_eq!(logs.messages().len(), 3);
+        assert_eq!(logs.messages()[0].view(), None);
+        assert_eq!(logs.view_messages("popup")[0].message, "oops");
+        assert_eq!(
+            logs.messages()[1].to_string(),
+            "[main] (file:///index.html, 3, 9) hello"
+        );
+
+        set_log_level(LevelFilter::Warn);
+        logs.clear();
+        dispatch(console("main", Level::Trace, "hello"));
+        dispatch(console("main", Level::Warn, "careful"));
+        let messages = logs.take();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].message, "careful");
+        assert!(logs.messages().is_empty());
+    }
+}
@@ -0,0 +1,276 @@
+//! MIME type detection for [Filesystem](crate::platform::Filesystem) implementations.
+
+use std::io::{self, Read};
+
+/// MIME type of unknown binary data.
+pub const DEFAULT: &str = "application/octet-stream";
+
+/// Get the MIME type for a file extension (without the dot, case insensitive).
+pub fn from_extension(extension: &str) -> Option<&'static str> {
+    Some(match extension.to_ascii_lowercase().as_str() {
+        "html" | "htm" => "text/html",
+        "xhtml" => "application/xhtml+xml",
+        "css" => "text/css",
+        "js" | "mjs" | "cjs" => "text/javascript",
+        "json" | "map" => "application/json",
+        "wasm" => "application/wasm",
+        "txt" => "text/plain",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" | "apng" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "eot" => "application/vnd.ms-fontobject",
+        "mp3" => "audio/mpeg",
+        "ogg" => "audio/ogg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        _ => return None,
+    })
+}
+
+/// Get the MIME type of a file from the extension of its path.
+pub fn from_path(path: &str) -> Option<&'static str> {
+    let name = path.rsplit(['/', '\\']).next()?;
+    from_extension(name.rsplit_once('.')?.1)
+}
+
+/// Tags starting an HTML document, as in the WHATWG MIME sniffing standard.
+const HTML_TAGS: &[&[u8]] = &[
+    b"<!DOCTYPE HTML",
+    b"<HTML",
+    b"<HEAD",
+    b"<SCRIPT",
+    b"<IFRAME",
+    b"<H1",
+    b"<DIV",
+    b"<FONT",
+    b"<TABLE",
+    b"<A",
+    b"<STYLE",
+    b"<TITLE",
+    b"<B",
+    b"<BODY",
+    b"<BR",
+    b"<P",
+    b"<!--",
+];
+
+/// Binary signatures, `?` matching any byte.
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1A\n", "image/png"),
+    (b"\xFF\xD8\xFF", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"RIFF????WEBPVP", "image/webp"),
+    (b"wOFF", "font/woff"),
+    (b"wOF2", "font/woff2"),
+    (b"\x00asm", "application/wasm"),
+    (b"%PDF-", "application/pdf"),
+    (b"ID3", "audio/mpeg"),
+    (b"OggS\x00", "audio/ogg"),
+    (b"RIFF????WAVE", "audio/wav"),
+    (b"\x1A\x45\xDF\xA3", "video/webm"),
+    (b"????ftypmp4", "video/mp4"),
+    (b"????ftypisom", "video/mp4"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1F\x8B\x08", "application/gzip"),
+];
+
+fn matches(data: &[u8], pattern: &[u8]) -> bool {
+    data.len() >= pattern.len() && pattern.iter().zip(data).all(|(&p, &d)| p == b'?' || p == d)
+}
+
+fn le_u16(data: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([data[at], data[at + 1]])
+}
+
+fn le_u32(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+}
+
+fn be_u16(data: &[u8], at: usize) -> u16 {
+    u16::from_be_bytes([data[at], data[at + 1]])
+}
+
+/// Formats whose signature is too short to be trusted alone, their header is validated.
+fn sniff_header(data: &[u8]) -> Option<&'static str> {
+    // BITMAPFILEHEADER, reserved fields, then the size of a known DIB header
+    if data.len() >= 18
+        && data.starts_with(b"BM")
+        && le_u32(data, 6) == 0
+        && matches!(le_u32(data, 14), 12 | 40 | 52 | 56 | 64 | 108 | 124)
+    {
+        return Some("image/bmp");
+    }
+    // ICONDIR with at least one image, then the first ICONDIRENTRY
+    if data.len() >= 22 && data.starts_with(b"\x00\x00\x01\x00") {
+        let count = le_u16(data, 4) as u32;
+        let entry = &data[6..22];
+        if count > 0
+            && entry[3] == 0
+            && le_u16(entry, 4) <= 1
+            && matches!(le_u16(entry, 6), 0 | 1 | 4 | 8 | 16 | 24 | 32)
+            && le_u32(entry, 8) > 0
+            && le_u32(entry, 12) >= 6 + 16 * count
+        {
+            return Some("image/x-icon");
+        }
+    }
+    // sfnt table directory, searchRange and entrySelector follow from numTables
+    if data.len() >= 12 && (data.starts_with(b"\x00\x01\x00\x00") || data.starts_with(b"OTTO")) {
+        let tables = be_u16(data, 4);
+        if tables > 0 {
+            let selector = 15 - tables.leading_zeros() as u16;
+            if be_u16(data, 6) == 16 << selector && be_u16(data, 8) == selector {
+                return Some(if data[0] == 0 { "font/ttf" } else { "font/otf" });
+            }
+        }
+    }
+    None
+}
+
+/// Guess the MIME type of a file from its first bytes (the first 512 bytes are enough).
+pub fn sniff(data: &[u8]) -> Option<&'static str> {
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(s, _)| matches(data, s)) {
+        return Some(mime);
+    }
+    if let Some(mime) = sniff_header(data) {
+        return Some(mime);
+    }
+
+    let text = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    let start = text
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(text.len());
+    let text = &text[start..];
+    for tag in HTML_TAGS {
+        // The tag must be followed by a space or a '>'
+        if text.len() > tag.len()
+            && text[..tag.len()].eq_ignore_ascii_case(tag)
+            && (text[tag.len()] == b' ' || text[tag.len()] == b'>')
+        {
+            return Some("text/html");
+        }
+    }
+    if text.starts_with(b"<?xml") {
+        return Some(if contains(text, b"<svg") {
+            "image/svg+xml"
+        } else {
+            "application/xml"
+        });
+    }
+    if text.starts_with(b"<svg") {
+        return Some("image/svg+xml");
+    }
+
+    // Text if there are no binary control characters, the data can end in the middle of a character
+    let binary = data
+        .iter()
+        .any(|&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\x0C' | b'\r' | 0x1B));
+    let valid = match std::str::from_utf8(data) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    };
+    if !data.is_empty() && !binary && valid {
+        return Some("text/plain");
+    }
+    None
+}
+
+fn contains(data: &[u8], pattern: &[u8]) -> bool {
+    data.windows(pattern.len()).any(|w| w == pattern)
+}
+
+/// Get the MIME type of a file from its path, or from its contents if the extension is unknown.
+pub fn guess(path: &str, data: &[u8]) -> &'static str {
+    from_path(path).or_else(|| sniff(data)).unwrap_or(DEFAULT)
+}
+
+/// Like [guess], only opening the file to sniff its first 512 bytes if the extension is unknown.
+pub fn guess_file<R: Read>(
+    path: &str,
+    open: impl FnOnce() -> io::Result<R>,
+) -> io::Result<&'static str> {
+    if let Some(mime) = from_path(path) {
+        return Ok(mime);
+    }
+    let mut head = Vec::with_capacity(512);
+    open()?.take(512).read_to_end(&mut head)?;
+    Ok(sniff(&head).unwrap_or(DEFAULT))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extensions() {
+        assert_eq!(from_path("index.HTML"), Some("text/html"));
+        assert_eq!(from_path("js/app.min.mjs"), Some("text/javascript"));
+        assert_eq!(from_path("fonts/Inter.woff2"), Some("font/woff2"));
+        assert_eq!(from_path("v1.0/LICENSE"), None);
+        assert_eq!(from_path("archive.unknown"), None);
+    }
+
+    #[test]
+    fn sniffing() {
+        assert_eq!(sniff(b"\n  <!doctype html><html>"), Some("text/html"));
+        assert_eq!(sniff(b"\xEF\xBB\xBF<p>Hello</p>"), Some("text/html"));
+        assert_eq!(
+            sniff(b"<?xml version=\"1.0\"?><svg>"),
+            Some("image/svg+xml")
+        );
+        assert_eq!(sniff(b"\x89PNG\r\n\x1A\n\0\0\0\x0DIHDR"), Some("image/png"));
+        assert_eq!(sniff(b"RIFF\x24\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff(b"\0asm\x01\0\0\0"), Some("application/wasm"));
+        assert_eq!(sniff("Licence : MIT ©".as_bytes()), Some("text/plain"));
+        // Cut in the middle of '©'
+        assert_eq!(sniff(&"MIT ©".as_bytes()[..5]), Some("text/plain"));
+        assert_eq!(sniff(b"\0\x01\x02\x03"), None);
+        assert_eq!(guess("LICENSE", b"\x7FELF\x02\x01"), DEFAULT);
+        let opened = |data: &'static [u8]| move || Ok(io::Cursor::new(data));
+        assert_eq!(guess_file("LICENSE", opened(b"MIT")).unwrap(), "text/plain");
+        assert_eq!(
+            guess_file("style.css", || -> io::Result<&[u8]> { unreachable!() }).unwrap(),
+            "text/css"
+        );
+    }
+
+    #[test]
+    fn validated_headers() {
+        let mut bmp = b"BM\x36\0\0\0\0\0\0\0\x36\0\0\0\x28\0\0\0".to_vec();
+        assert_eq!(sniff(&bmp), Some("image/bmp"));
+        bmp[14] = 0x29;
+        assert_eq!(sniff(&bmp), None);
+        assert_eq!(sniff(b"BMW 320i, 2004"), Some("text/plain"));
+
+        let ico = b"\0\0\x01\0\x01\0\x10\x10\0\0\x01\0\x20\0\x68\x04\0\0\x16\0\0\0";
+        assert_eq!(sniff(ico), Some("image/x-icon"));
+        assert_eq!(sniff(&ico[..4]), None);
+        assert_eq!(
+            sniff(b"\0\0\x01\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0"),
+            None
+        );
+
+        // 11 tables : searchRange 128, entrySelector 3
+        let ttf = b"\0\x01\0\0\0\x0B\0\x80\0\x03\0\x30";
+        assert_eq!(sniff(ttf), Some("font/ttf"));
+        assert_eq!(sniff(b"OTTO\0\x0B\0\x80\0\x03\0\x30"), Some("font/otf"));
+        assert_eq!(sniff(b"\0\x01\0\0\x12\x34\x56\x78\0\0\0\0"), None);
+    }
+}
@@ -0,0 +1,49 @@
+use ultralight_sys::{ulEnablePlatformFileSystem, ulEnablePlatformFontLoader};
+
+use crate::ULString;
+
+pub use self::archive::*;
+pub use self::clipboard::*;
+pub use self::embedded::*;
+pub use self::filesystem::*;
+pub use self::font::*;
+pub use self::gpu::*;
+pub use self::layered::*;
+pub use self::logger::*;
+pub use self::sandbox::*;
+pub use self::scheme::*;
+#[cfg(unix)]
+pub use self::shm::*;
+pub use self::surface::*;
+
+mod archive;
+mod clipboard;
+mod embedded;
+mod filesystem;
+mod font;
+mod gpu;
+mod layered;
+mod logger;
+pub mod mime;
+mod sandbox;
+mod scheme;
+#[cfg(unix)]
+mod shm;
+mod surface;
+
+/// Initializes the platform font loader and sets it as the current FontLoader.
+pub fn enable_fontloader() {
+    unsafe {
+        ulEnablePlatformFontLoader();
+    }
+}
+
+/// Initializes the platform file system (needed for loading file:/// URLs)
+/// and sets it as the current FileSystem.
+/// You can specify a base directory path to resolve relative paths against.
+pub fn enable_default_filesystem(path: &str) {
+    unsafe {
+        let path = ULString::from(path);
+        ulEnablePlatformFileSystem(path.raw());
+    }
+}
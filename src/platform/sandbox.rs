@@ -0,0 +1,297 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::platform::{mime, File, Filesystem};
+
+/// Filesystem operation recorded by a [SandboxFilesystem].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FileOperation {
+    FileExists,
+    Open,
+}
+
+/// Why a [SandboxFilesystem] refused a path.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DenialReason {
+    /// The path is absolute, or goes up with `..`.
+    Traversal,
+    /// The path resolves outside of the root, through a symlink.
+    OutsideRoot,
+    /// The file extension isn't in the allow-list.
+    Extension,
+}
+
+impl fmt::Display for DenialReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DenialReason::Traversal => "path traversal",
+            DenialReason::OutsideRoot => "outside of the sandbox root",
+            DenialReason::Extension => "extension not allowed",
+        })
+    }
+}
+
+/// Outcome of a filesystem operation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FileAccess {
+    Allowed,
+    NotFound,
+    Denied(DenialReason),
+}
+
+/// An entry of the audit log of a [SandboxFilesystem].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessRecord {
+    pub operation: FileOperation,
+    /// Path as requested by Ultralight.
+    pub path: String,
+    pub access: FileAccess,
+}
+
+/// Shared handle to the audit log of a [SandboxFilesystem], still usable once the filesystem has been
+/// given to [set_filesystem_impl](crate::platform::set_filesystem_impl).
+#[derive(Debug, Clone, Default)]
+pub struct AuditLog {
+    records: Arc<Mutex<Vec<AccessRecord>>>,
+}
+
+impl AuditLog {
+    /// Copy of the recorded accesses, oldest first.
+    pub fn records(&self) -> Vec<AccessRecord> {
+        self.records.lock().unwrap().clone()
+    }
+
+    /// Recorded accesses that were denied.
+    pub fn denied(&self) -> Vec<AccessRecord> {
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| matches!(r.access, FileAccess::Denied(_)))
+            .cloned()
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        self.records.lock().unwrap().clear();
+    }
+
+    fn record(&self, operation: FileOperation, path: &str, access: FileAccess) {
+        if let FileAccess::Denied(denial) = access {
+            log::warn!("Denied access to '{}' : {}", path, denial);
+        }
+        self.records.lock().unwrap().push(AccessRecord {
+            operation,
+            path: path.to_string(),
+            access,
+        });
+    }
+}
+
+/// A [Filesystem] serving a directory, for partially untrusted content.
+///
+/// Paths going up with `..` or resolving outside of the root through symlinks are denied,
+/// as well as files whose extension isn't allowed. Every `file_exists` and `open` call is recorded
+/// in an [AuditLog].
+///
+/// Opened files are checked again once opened on Linux only : elsewhere, a symlink swapped in
+/// by another process while a file is being opened isn't detected.
+///
+/// ```no_run
+/// # use ultralight_rs::platform::{self, SandboxFilesystem};
+/// # fn test() -> std::io::Result<()> {
+/// let sandbox = SandboxFilesystem::new("assets")?.allow_extensions(&["html", "css", "js", "png"]);
+/// let audit = sandbox.audit_log();
+/// platform::set_filesystem_impl(sandbox);
+/// // Load pages...
+/// assert!(audit.denied().is_empty());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SandboxFilesystem {
+    root: PathBuf,
+    extensions: Option<Vec<String>>,
+    audit: AuditLog,
+}
+
+impl SandboxFilesystem {
+    /// Serve the files under `root`, which must exist.
+    pub fn new<P: AsRef<Path>>(root: P) -> io::Result<Self> {
+        Ok(SandboxFilesystem {
+            root: root.as_ref().canonicalize()?,
+            extensions: None,
+            audit: AuditLog::default(),
+        })
+    }
+
+    /// Only serve files with one of these extensions (case insensitive, without the dot).
+    /// All files are served by default.
+    pub fn allow_extensions(mut self, extensions: &[&str]) -> Self {
+        self.extensions = Some(extensions.iter().map(|e| e.to_ascii_lowercase()).collect());
+        self
+    }
+
+    /// Canonical path of the root directory.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Handle to the log of all accesses.
+    pub fn audit_log(&self) -> AuditLog {
+        self.audit.clone()
+    }
+
+    /// Canonical path of an existing file, checking it is allowed.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, FileAccess> {
+        let relative = Path::new(path.trim_start_matches('/'));
+        if path.contains('\0')
+            || !relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(FileAccess::Denied(DenialReason::Traversal));
+        }
+        if let Some(extensions) = &self.extensions {
+            let extension = relative
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(str::to_ascii_lowercase);
+            if !extension.is_some_and(|e| extensions.contains(&e)) {
+                return Err(FileAccess::Denied(DenialReason::Extension));
+            }
+        }
+        // Resolves symlinks
+        let resolved = self
+            .root
+            .join(relative)
+            .canonicalize()
+            .map_err(|_| FileAccess::NotFound)?;
+        if !resolved.starts_with(&self.root) {
+            return Err(FileAccess::Denied(DenialReason::OutsideRoot));
+        }
+        if !resolved.is_file() {
+            return Err(FileAccess::NotFound);
+        }
+        Ok(resolved)
+    }
+
+    /// Open a path given by [SandboxFilesystem::resolve].
+    ///
+    /// A path component may be replaced by a symlink between `resolve` and the open :
+    /// on Linux the path of the opened file is checked against the root again,
+    /// other platforms only rely on `resolve`.
+    fn open_resolved(&self, resolved: &Path) -> Result<fs::File, FileAccess> {
+        let file = fs::File::open(resolved).map_err(|_| FileAccess::NotFound)?;
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::io::AsRawFd;
+            // Unavailable when /proc isn't mounted
+            if let Ok(opened) = fs::read_link(format!("/proc/self/fd/{}", file.as_raw_fd())) {
+                if !opened.starts_with(&self.root) {
+                    return Err(FileAccess::Denied(DenialReason::OutsideRoot));
+                }
+            }
+        }
+        Ok(file)
+    }
+}
+
+impl Filesystem for SandboxFilesystem {
+    fn file_exists(&self, path: &str) -> bool {
+        let result = self.resolve(path);
+        let access = result
+            .as_ref()
+            .err()
+            .copied()
+            .unwrap_or(FileAccess::Allowed);
+        self.audit.record(FileOperation::FileExists, path, access);
+        result.is_ok()
+    }
+
+    fn mime_type(&self, path: &str) -> Option<String> {
+        // Not recorded, only called for files that exist
+        let resolved = self.resolve(path).ok()?;
+        let mime = mime::guess_file(path, || {
+            self.open_resolved(&resolved)
+                .map_err(|_| io::Error::from(io::ErrorKind::NotFound))
+        })
+        .ok()?;
+        Some(mime.to_string())
+    }
+
+    fn open(&self, path: &str) -> io::Result<Box<dyn File>> {
+        let result = self
+            .resolve(path)
+            .and_then(|resolved| self.open_resolved(&resolved));
+        let access = result
+            .as_ref()
+            .err()
+            .copied()
+            .unwrap_or(FileAccess::Allowed);
+        self.audit.record(FileOperation::Open, path, access);
+        match result {
+            Ok(file) => Ok(Box::new(file)),
+            Err(FileAccess::Denied(denial)) => Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                denial.to_string(),
+            )),
+            Err(_) => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sandbox() {
+        let dir = std::env::temp_dir().join(format!("ultralight-sandbox-{}", std::process::id()));
+        let root = dir.join("root");
+        fs::create_dir_all(root.join("css")).unwrap();
+        fs::write(root.join("index.html"), "<p>Hello</p>").unwrap();
+        fs::write(root.join("css/style.css"), "p {}").unwrap();
+        fs::write(root.join("notes.txt"), "notes").unwrap();
+        fs::write(dir.join("secret.html"), "secret").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.join("secret.html"), root.join("link.html")).unwrap();
+
+        let sandbox = SandboxFilesystem::new(&root)
+            .unwrap()
+            .allow_extensions(&["html", "CSS"]);
+        let audit = sandbox.audit_log();
+
+        assert!(sandbox.file_exists("index.html"));
+        assert!(sandbox.open("/css/style.css").is_ok());
+        assert!(!sandbox.file_exists("missing.html"));
+        assert!(!sandbox.file_exists("notes.txt"));
+        assert!(!sandbox.file_exists("css/../../secret.html"));
+        let denied = sandbox.open("../secret.html").err().unwrap();
+        assert_eq!(denied.kind(), io::ErrorKind::PermissionDenied);
+        #[cfg(unix)]
+        assert!(!sandbox.file_exists("link.html"));
+        assert_eq!(sandbox.mime_type("index.html").unwrap(), "text/html");
+
+        let accesses: Vec<_> = audit.records().into_iter().map(|r| r.access).collect();
+        let mut expected = vec![
+            FileAccess::Allowed,
+            FileAccess::Allowed,
+            FileAccess::NotFound,
+            FileAccess::Denied(DenialReason::Extension),
+            FileAccess::Denied(DenialReason::Traversal),
+            FileAccess::Denied(DenialReason::Traversal),
+        ];
+        if cfg!(unix) {
+            expected.push(FileAccess::Denied(DenialReason::OutsideRoot));
+        }
+        assert_eq!(accesses, expected);
+        assert_eq!(audit.records()[1].operation, FileOperation::Open);
+        assert_eq!(audit.denied().len(), expected.len() - 3);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,327 @@
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::io::{self, Cursor};
+use std::sync::Mutex;
+
+use crate::platform::{File, Filesystem};
+
+/// A request for a custom scheme URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemeRequest {
+    /// Full URL, eg `app://api/users?page=2`.
+    pub url: String,
+    /// Path relative to the registered prefix, eg `users` for a handler registered at `app://api/`.
+    pub path: String,
+    /// Query string, without the `?`.
+    pub query: Option<String>,
+}
+
+/// Contents generated for a [SchemeRequest].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemeResponse {
+    pub data: Vec<u8>,
+    pub mime_type: String,
+}
+
+impl SchemeResponse {
+    pub fn new<D: Into<Vec<u8>>>(data: D, mime_type: &str) -> Self {
+        SchemeResponse {
+            data: data.into(),
+            mime_type: mime_type.to_string(),
+        }
+    }
+
+    pub fn html(html: &str) -> Self {
+        SchemeResponse::new(html, "text/html")
+    }
+
+    pub fn json(json: &str) -> Self {
+        SchemeResponse::new(json, "application/json")
+    }
+
+    pub fn text(text: &str) -> Self {
+        SchemeResponse::new(text, "text/plain")
+    }
+}
+
+/// Number of responses kept between `file_exists` and `open`.
+const PENDING_RESPONSES: usize = 32;
+
+type Handler = Box<dyn Fn(&SchemeRequest) -> Option<SchemeResponse> + Send + Sync>;
+
+struct Route {
+    scheme: String,
+    /// Prefix after `scheme://`.
+    prefix: String,
+    handler: Handler,
+}
+
+/// A registry of Rust handlers generating the resources of custom URL schemes (eg `app://api/users`).
+///
+/// Ultralight 1.2 only gives file:/// URLs to the [Filesystem], so custom schemes are mapped to
+/// files : `app://api/users` is served as `file:///app/api/users`.
+/// Use the registry as the filesystem (or mount it at `/` in a
+/// [LayeredFilesystem](crate::platform::LayeredFilesystem)), and inject [SchemeHandlers::script]
+/// in pages : it rewrites the custom scheme URLs given to `fetch()`, `XMLHttpRequest`
+/// and `src`/`href` attributes. Pages can also use the `file:///app/...` URLs directly.
+///
+/// Handlers are called as soon as Ultralight checks that the file exists, even if it never
+/// opens it, so they should be free of side effects. The response is kept until the file is opened,
+/// only the last responses are kept.
+///
+/// ```no_run
+/// # use ultralight_rs::platform::{self, SchemeHandlers, SchemeResponse};
+/// # use ultralight_rs::View;
+/// # fn test(view: &mut View) -> std::io::Result<()> {
+/// let handlers = SchemeHandlers::new()
+///     .register("app://api/users", |_| Some(SchemeResponse::json(r#"[{"name": "Ada"}]"#)))?
+///     .register("app://images/", |request| {
+///         std::fs::read(format!("images/{}", request.path))
+///             .ok()
+///             .map(|data| SchemeResponse::new(data, "image/png"))
+///     })?;
+/// let script = handlers.script();
+/// platform::set_filesystem_impl(handlers);
+/// view.on_window_ready(&mut |mut view: View, _, _, _| {
+///     view.evaluate_script(&script).unwrap();
+/// });
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct SchemeHandlers {
+    routes: Vec<Route>,
+    /// Responses generated by `file_exists`, waiting to be opened, oldest first.
+    pending: Mutex<VecDeque<(String, SchemeResponse)>>,
+}
+
+impl SchemeHandlers {
+    pub fn new() -> Self {
+        SchemeHandlers::default()
+    }
+
+    /// Call `handler` for the URLs starting with `url_prefix` (eg `app://api/`).
+    /// The longest matching prefix wins, a handler returning `None` gives a "not found".
+    /// Fails if the prefix doesn't start with a scheme.
+    pub fn register<F>(mut self, url_prefix: &str, handler: F) -> io::Result<Self>
+    where
+        F: Fn(&SchemeRequest) -> Option<SchemeResponse> + Send + Sync + 'static,
+    {
+        let (scheme, prefix) = url_prefix
+            .split_once("://")
+            .filter(|(scheme, _)| !scheme.is_empty())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("'{}' doesn't start with a scheme, eg app://", url_prefix),
+                )
+            })?;
+        self.routes.push(Route {
+            scheme: scheme.to_ascii_lowercase(),
+            prefix: prefix.to_string(),
+            handler: Box::new(handler),
+        });
+        self.routes.sort_by_key(|r| Reverse(r.prefix.len()));
+        Ok(self)
+    }
+
+    /// Schemes with registered handlers.
+    pub fn schemes(&self) -> Vec<&str> {
+        let mut schemes: Vec<&str> = self.routes.iter().map(|r| r.scheme.as_str()).collect();
+        schemes.sort_unstable();
+        schemes.dedup();
+        schemes
+    }
+
+    /// File path serving a custom scheme URL, eg `app/api/users` for `app://api/users`.
+    pub fn file_path(url: &str) -> Option<String> {
+        let (scheme, rest) = url.split_once("://")?;
+        Some(format!("{}/{}", scheme.to_ascii_lowercase(), rest))
+    }
+
+    /// Call the handler of a file path.
+    pub fn handle(&self, path: &str) -> Option<SchemeResponse> {
+        let path = path.trim_start_matches('/');
+        let (scheme, rest) = path.split_once('/')?;
+        let (rest, query) = match rest.split_once('?') {
+            Some((rest, query)) => (rest, Some(query.to_string())),
+            None => (rest, None),
+        };
+        let route = self
+            .routes
+            .iter()
+            .find(|r| r.scheme == scheme && rest.starts_with(r.prefix.as_str()))?;
+        (route.handler)(&SchemeRequest {
+            url: format!("{}://{}", scheme, &path[scheme.len() + 1..]),
+            path: rest[route.prefix.len()..].to_string(),
+            query,
+        })
+    }
+
+    fn keep_response(&self, path: &str, response: SchemeResponse) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|(p, _)| p != path);
+        if pending.len() == PENDING_RESPONSES {
+            pending.pop_front();
+        }
+        pending.push_back((path.to_string(), response));
+    }
+
+    fn take_response(&self, path: &str) -> Option<SchemeResponse> {
+        let mut pending = self.pending.lock().unwrap();
+        let index = pending.iter().position(|(p, _)| p == path)?;
+        pending.remove(index).map(|(_, response)| response)
+    }
+
+    /// JavaScript rewriting the URLs of the registered schemes to file:/// URLs.
+    /// Evaluate it when the window object is ready, before the page scripts run.
+    pub fn script(&self) -> String {
+        let schemes: Vec<String> = self
+            .schemes()
+            .iter()
+            .map(|s| format!("\"{}\"", s))
+            .collect();
+        SCRIPT.replace("SCHEMES", &schemes.join(", "))
+    }
+}
+
+impl Filesystem for SchemeHandlers {
+    fn file_exists(&self, path: &str) -> bool {
+        match self.handle(path) {
+            Some(response) => {
+                self.keep_response(path, response);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn mime_type(&self, path: &str) -> Option<String> {
+        let pending = self.pending.lock().unwrap();
+        if let Some((_, response)) = pending.iter().find(|(p, _)| p == path) {
+            return Some(response.mime_type.clone());
+        }
+        drop(pending);
+        let response = self.handle(path)?;
+        let mime_type = response.mime_type.clone();
+        self.keep_response(path, response);
+        Some(mime_type)
+    }
+
+    fn open(&self, path: &str) -> io::Result<Box<dyn File>> {
+        match self.take_response(path).or_else(|| self.handle(path)) {
+            Some(response) => Ok(Box::new(Cursor::new(response.data))),
+            None => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+}
+
+const SCRIPT: &str = r#"(function () {
+    var schemes = [SCHEMES];
+    function rewrite(url) {
+        if (typeof url !== "string") return url;
+        for (var i = 0; i < schemes.length; i++) {
+            var prefix = schemes[i] + "://";
+            if (url.substring(0, prefix.length).toLowerCase() === prefix)
+                return "file:///" + schemes[i] + "/" + url.substring(prefix.length);
+        }
+        return url;
+    }
+    if (window.fetch) {
+        var fetch = window.fetch;
+        window.fetch = function (input, init) {
+            return fetch.call(this, rewrite(input), init);
+        };
+    }
+    var open = XMLHttpRequest.prototype.open;
+    XMLHttpRequest.prototype.open = function (method, url) {
+        arguments[1] = rewrite(url);
+        return open.apply(this, arguments);
+    };
+    function fix(element) {
+        ["src", "href"].forEach(function (name) {
+            var value = element.getAttribute(name);
+            if (value && rewrite(value) !== value) element.setAttribute(name, rewrite(value));
+        });
+    }
+    new MutationObserver(function (records) {
+        records.forEach(function (record) {
+            if (record.type === "attributes") return fix(record.target);
+            record.addedNodes.forEach(function (node) {
+                if (node.nodeType !== 1) return;
+                fix(node);
+                Array.prototype.forEach.call(node.querySelectorAll("[src], [href]"), fix);
+            });
+        });
+    }).observe(document, { childList: true, subtree: true, attributes: true, attributeFilter: ["src", "href"] });
+})();
+"#;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn scheme_handlers() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let handlers = SchemeHandlers::new()
+            .register("app://api/", |request| {
+                Some(SchemeResponse::json(&format!(
+                    "{{\"path\": \"{}\", \"query\": \"{}\"}}",
+                    request.path,
+                    request.query.as_deref().unwrap_or("")
+                )))
+            })
+            .unwrap()
+            .register("app://api/users", move |request| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                assert_eq!(request.url, "app://api/users");
+                Some(SchemeResponse::json("[]"))
+            })
+            .unwrap()
+            .register("app://missing/", |_| None)
+            .unwrap();
+
+        assert_eq!(
+            SchemeHandlers::file_path("app://api/users").unwrap(),
+            "app/api/users"
+        );
+        assert_eq!(handlers.schemes(), ["app"]);
+        assert!(handlers.script().contains("var schemes = [\"app\"];"));
+
+        // Generated once for the whole exists/mime/open sequence
+        assert!(handlers.file_exists("app/api/users"));
+        assert_eq!(
+            handlers.mime_type("app/api/users").unwrap(),
+            "application/json"
+        );
+        let mut data = String::new();
+        handlers
+            .open("app/api/users")
+            .unwrap()
+            .read_to_string(&mut data)
+            .unwrap();
+        assert_eq!(data, "[]");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        assert_eq!(
+            handlers.handle("/app/api/items?page=2").unwrap().data,
+            b"{\"path\": \"items\", \"query\": \"page=2\"}"
+        );
+        assert!(!handlers.file_exists("app/missing/file"));
+        assert!(!handlers.file_exists("other/api/users"));
+        assert!(handlers.open("index.html").is_err());
+        assert!(SchemeHandlers::new().register("api/", |_| None).is_err());
+
+        // Existence checks without an open don't pile up
+        for i in 0..100 {
+            assert!(handlers.file_exists(&format!("app/api/items/{}", i)));
+        }
+        assert_eq!(handlers.pending.lock().unwrap().len(), PENDING_RESPONSES);
+    }
+}
@@ -0,0 +1,505 @@
+//! Surfaces backed by POSIX shared memory, for compositing frames in another process.
+
+use std::ffi::{c_void, CString};
+use std::io;
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{fence, AtomicI32, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use ultralight_sys::ULIntRect;
+
+use crate::platform::SurfaceDefinition;
+use crate::{Image, PixelFormat};
+
+/// 'ULSM', identifies a segment created by [SharedMemorySurface].
+const MAGIC: u32 = 0x554C_534D;
+const VERSION: u32 = 1;
+/// Pixels start right after the header, on a cache line boundary.
+const HEADER_SIZE: usize = 64;
+
+/// Prefix of the segment names, see [SharedMemorySurface::set_name_prefix].
+static NAME_PREFIX: Mutex<String> = Mutex::new(String::new());
+static SEGMENT_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Layout of the start of the segment.
+#[repr(C)]
+struct Header {
+    magic: AtomicU32,
+    version: AtomicU32,
+    width: AtomicU32,
+    height: AtomicU32,
+    stride: AtomicU32,
+    dirty_left: AtomicI32,
+    dirty_top: AtomicI32,
+    dirty_right: AtomicI32,
+    dirty_bottom: AtomicI32,
+    /// Number of written frames times 2, odd while the pixels are being written.
+    sequence: AtomicU64,
+}
+
+/// A memory mapping of a shared memory segment.
+struct Mapping {
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl Mapping {
+    fn new(fd: i32, len: usize, writable: bool) -> io::Result<Self> {
+        let prot = if writable {
+            libc::PROT_READ | libc::PROT_WRITE
+        } else {
+            libc::PROT_READ
+        };
+        let (flags, fd) = if fd < 0 {
+            (libc::MAP_PRIVATE | libc::MAP_ANONYMOUS, -1)
+        } else {
+            (libc::MAP_SHARED, fd)
+        };
+        let ptr = unsafe { libc::mmap(ptr::null_mut(), len, prot, flags, fd, 0) };
+        if ptr == libc::MAP_FAILED {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(Mapping {
+                ptr: ptr as *mut u8,
+                len,
+            })
+        }
+    }
+
+    fn header(&self) -> &Header {
+        unsafe { &*(self.ptr as *const Header) }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr as *mut c_void, self.len);
+        }
+    }
+}
+
+fn segment_size(width: u32, height: u32) -> usize {
+    HEADER_SIZE + width as usize * height as usize * 4
+}
+
+fn shm_name(name: &str) -> io::Result<CString> {
+    CString::new(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// A [SurfaceDefinition] writing pixels to a POSIX shared memory segment,
+/// so another process can display them with a [SharedMemoryReader] without copying.
+///
+/// The segment starts with a 64 bytes header (width, height, stride, dirty rect and frame counter)
+/// followed by the premultiplied BGRA pixels. It is unlinked when the surface is destroyed.
+///
+/// A frame is counted each time the library unlocks the pixels. It also locks them to read them,
+/// so the counter can advance without the pixels changing.
+///
+/// ```no_run
+/// # use ultralight_rs::platform::{self, SharedMemorySurface};
+/// # use ultralight_rs::View;
+/// # fn test(view: &View) {
+/// SharedMemorySurface::set_name_prefix("my-app");
+/// platform::set_surface_definition_impl::<SharedMemorySurface>();
+/// // Create the renderer and views, render...
+/// let surface = view.surface();
+/// // Safe : no mutable reference to the surface exists and the renderer is idle
+/// let shm = unsafe { surface.definition::<SharedMemorySurface>() }.unwrap();
+/// let name = shm.name().unwrap().to_string(); // Send this to the consumer process
+/// # }
+/// ```
+pub struct SharedMemorySurface {
+    name: Option<String>,
+    fd: i32,
+    mapping: Mapping,
+    width: u32,
+    height: u32,
+}
+
+// The mapping is only accessed through &self/&mut self
+unsafe impl Send for SharedMemorySurface {}
+
+impl SharedMemorySurface {
+    /// Set the prefix of the names of the segments created from now on.
+    /// Segments are named `/<prefix>-<pid>-<n>`, the prefix defaults to `ultralight`.
+    pub fn set_name_prefix(prefix: &str) {
+        *NAME_PREFIX.lock().unwrap() = prefix.to_string();
+    }
+
+    /// Create a surface in a new shared memory segment.
+    pub fn open(width: u32, height: u32) -> io::Result<Self> {
+        let name = {
+            let prefix = NAME_PREFIX.lock().unwrap();
+            format!(
+                "/{}-{}-{}",
+                if prefix.is_empty() {
+                    "ultralight"
+                } else {
+                    prefix.as_str()
+                },
+                std::process::id(),
+                SEGMENT_COUNT.fetch_add(1, Ordering::Relaxed)
+            )
+        };
+        let cname = shm_name(&name)?;
+        let fd = unsafe {
+            libc::shm_open(
+                cname.as_ptr(),
+                libc::O_CREAT | libc::O_EXCL | libc::O_RDWR,
+                0o600,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        match Self::map(fd, width, height) {
+            Ok(mapping) => Ok(Self::init(Some(name), fd, mapping, width, height)),
+            Err(e) => unsafe {
+                libc::close(fd);
+                libc::shm_unlink(cname.as_ptr());
+                Err(e)
+            },
+        }
+    }
+
+    /// Size the segment and map it. Segments only grow so readers never access unmapped pages.
+    fn map(fd: i32, width: u32, height: u32) -> io::Result<Mapping> {
+        let size = segment_size(width, height);
+        if fd >= 0 {
+            let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+            if unsafe { libc::fstat(fd, &mut stat) } < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if (stat.st_size as usize) < size
+                && unsafe { libc::ftruncate(fd, size as libc::off_t) } < 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Mapping::new(fd, size, true)
+    }
+
+    fn init(name: Option<String>, fd: i32, mapping: Mapping, width: u32, height: u32) -> Self {
+        let surface = SharedMemorySurface {
+            name,
+            fd,
+            mapping,
+            width,
+            height,
+        };
+        let header = surface.mapping.header();
+        header.width.store(width, Ordering::Relaxed);
+        header.height.store(height, Ordering::Relaxed);
+        header.stride.store(width * 4, Ordering::Relaxed);
+        header.version.store(VERSION, Ordering::Relaxed);
+        header.magic.store(MAGIC, Ordering::Release);
+        surface
+    }
+
+    /// Name of the shared memory segment, to be opened with [SharedMemoryReader::open].
+    /// `None` if the segment couldn't be created and the surface fell back to private memory.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Number of frames written so far.
+    pub fn frame(&self) -> u64 {
+        self.mapping.header().sequence.load(Ordering::Acquire) / 2
+    }
+
+    /// Share the dirty rect of the last frame, eg from
+    /// [Surface::get_dirty_bounds](crate::Surface::get_dirty_bounds) after
+    /// [Renderer::render](crate::Renderer::render), so readers can copy only what changed.
+    ///
+    /// This is optional, frames are shared as soon as the library unlocks the pixels.
+    pub fn publish(&mut self, dirty: ULIntRect) {
+        let header = self.mapping.header();
+        header.dirty_left.store(dirty.left, Ordering::Relaxed);
+        header.dirty_top.store(dirty.top, Ordering::Relaxed);
+        header.dirty_right.store(dirty.right, Ordering::Relaxed);
+        header.dirty_bottom.store(dirty.bottom, Ordering::Release);
+    }
+}
+
+impl SurfaceDefinition for SharedMemorySurface {
+    fn create(width: u32, height: u32) -> Self {
+        SharedMemorySurface::open(width, height).unwrap_or_else(|e| {
+            log::error!(
+                "Couldn't create a shared memory surface, falling back to private memory : {}",
+                e
+            );
+            let mapping = SharedMemorySurface::map(-1, width, height)
+                .expect("Couldn't allocate surface memory");
+            SharedMemorySurface::init(None, -1, mapping, width, height)
+        })
+    }
+
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn lock_pixels(&mut self) -> &mut [u8] {
+        let header = self.mapping.header();
+        let sequence = header.sequence.load(Ordering::Relaxed);
+        header.sequence.store(sequence | 1, Ordering::Release);
+        let len = self.width as usize * self.height as usize * 4;
+        unsafe { slice::from_raw_parts_mut(self.mapping.ptr.add(HEADER_SIZE), len) }
+    }
+
+    fn unlock_pixels(&mut self) {
+        // Round up to the next even value, ending the write started in lock_pixels or resize
+        let header = self.mapping.header();
+        let sequence = header.sequence.load(Ordering::Relaxed);
+        if sequence % 2 == 1 {
+            header.sequence.store(sequence + 1, Ordering::Release);
+        }
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        // The size change starts a write, ended by the next unlock once painted at the new size,
+        // so readers never mix the old and new sizes
+        let header = self.mapping.header();
+        let sequence = header.sequence.load(Ordering::Relaxed);
+        header.sequence.store(sequence | 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        if segment_size(width, height) > self.mapping.len {
+            match SharedMemorySurface::map(self.fd, width, height) {
+                Ok(mapping) => {
+                    // Private memory doesn't keep the header when mapped again
+                    mapping
+                        .header()
+                        .sequence
+                        .store(sequence | 1, Ordering::Relaxed);
+                    self.mapping = mapping;
+                }
+                Err(e) => {
+                    log::error!("Couldn't resize shared memory surface : {}", e);
+                    header.sequence.store(sequence, Ordering::Release);
+                    return;
+                }
+            }
+        }
+        self.width = width;
+        self.height = height;
+        let header = self.mapping.header();
+        header.width.store(width, Ordering::Relaxed);
+        header.height.store(height, Ordering::Relaxed);
+        header.stride.store(width * 4, Ordering::Release);
+    }
+}
+
+impl Drop for SharedMemorySurface {
+    fn drop(&mut self) {
+        if self.fd >= 0 {
+            unsafe {
+                libc::close(self.fd);
+            }
+        }
+        if let Some(name) = self.name.as_ref().and_then(|n| shm_name(n).ok()) {
+            unsafe {
+                libc::shm_unlink(name.as_ptr());
+            }
+        }
+    }
+}
+
+/// Description of the frame currently in a shared memory segment.
+#[derive(Debug, Copy, Clone)]
+pub struct SharedFrameInfo {
+    /// Number of frames written so far.
+    pub frame: u64,
+    pub width: u32,
+    pub height: u32,
+    /// Number of bytes between rows.
+    pub stride: u32,
+    /// Region painted in the last frame, as shared with [SharedMemorySurface::publish].
+    pub dirty: ULIntRect,
+}
+
+/// Read-only view of a [SharedMemorySurface], usually from another process.
+pub struct SharedMemoryReader {
+    fd: i32,
+    mapping: Mapping,
+}
+
+unsafe impl Send for SharedMemoryReader {}
+
+impl SharedMemoryReader {
+    /// Map the segment with the given name, see [SharedMemorySurface::name].
+    pub fn open(name: &str) -> io::Result<Self> {
+        let cname = shm_name(name)?;
+        let fd = unsafe { libc::shm_open(cname.as_ptr(), libc::O_RDONLY, 0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let reader = Self::map(fd).map(|mapping| SharedMemoryReader { fd, mapping });
+        if reader.is_err() {
+            unsafe {
+                libc::close(fd);
+            }
+        }
+        let reader = reader?;
+        if reader.mapping.header().magic.load(Ordering::Acquire) != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not an ultralight surface segment",
+            ));
+        }
+        Ok(reader)
+    }
+
+    fn map(fd: i32) -> io::Result<Mapping> {
+        let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+        if unsafe { libc::fstat(fd, &mut stat) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if (stat.st_size as usize) < HEADER_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "shared memory segment is too small",
+            ));
+        }
+        Mapping::new(fd, stat.st_size as usize, false)
+    }
+
+    /// Number of frames written so far, cheap to poll.
+    pub fn frame(&self) -> u64 {
+        self.mapping.header().sequence.load(Ordering::Acquire) / 2
+    }
+
+    /// Describe the current frame, mapping the segment again if the surface grew.
+    pub fn info(&mut self) -> io::Result<SharedFrameInfo> {
+        let header = self.mapping.header();
+        let frame = header.sequence.load(Ordering::Acquire) / 2;
+        let stride = header.stride.load(Ordering::Acquire);
+        let width = header.width.load(Ordering::Relaxed);
+        let height = header.height.load(Ordering::Relaxed);
+        let dirty = ULIntRect {
+            left: header.dirty_left.load(Ordering::Relaxed),
+            top: header.dirty_top.load(Ordering::Relaxed),
+            right: header.dirty_right.load(Ordering::Relaxed),
+            bottom: header.dirty_bottom.load(Ordering::Relaxed),
+        };
+        let len = HEADER_SIZE + stride as usize * height as usize;
+        if len > self.mapping.len {
+            self.mapping = Self::map(self.fd)?;
+            if len > self.mapping.len {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "frame is larger than the shared memory segment",
+                ));
+            }
+        }
+        Ok(SharedFrameInfo {
+            frame,
+            width,
+            height,
+            stride,
+            dirty,
+        })
+    }
+
+    /// Pixels of the segment, without copying.
+    ///
+    /// The producer may be painting the next frame at the same time,
+    /// use [SharedMemoryReader::to_image] to get a consistent copy.
+    pub fn pixels(&mut self) -> io::Result<&[u8]> {
+        let info = self.info()?;
+        Ok(self.frame_pixels(&info))
+    }
+
+    /// Pixels of the segment for a header read with [SharedMemoryReader::info].
+    fn frame_pixels(&self, info: &SharedFrameInfo) -> &[u8] {
+        let len = info.stride as usize * info.height as usize;
+        unsafe { slice::from_raw_parts(self.mapping.ptr.add(HEADER_SIZE), len) }
+    }
+
+    /// Copy the last written frame. Retries while the producer is writing,
+    /// returns `None` if no consistent copy could be made after a few attempts.
+    pub fn to_image(&mut self) -> io::Result<Option<Image>> {
+        for _ in 0..16 {
+            let before = self.mapping.header().sequence.load(Ordering::Acquire);
+            if before % 2 == 1 {
+                std::thread::yield_now();
+                continue;
+            }
+            let info = self.info()?;
+            let data = self.frame_pixels(&info).to_vec();
+            fence(Ordering::Acquire);
+            let after = self.mapping.header().sequence.load(Ordering::Relaxed);
+            if before == after {
+                return Ok(Some(Image {
+                    width: info.width,
+                    height: info.height,
+                    stride: info.stride,
+                    format: PixelFormat::Bgra8,
+                    premultiplied: true,
+                    data,
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Drop for SharedMemoryReader {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn share_frames() {
+        SharedMemorySurface::set_name_prefix("ultralight-rs-test");
+        let mut surface = SharedMemorySurface::open(2, 2).unwrap();
+        let mut reader = SharedMemoryReader::open(surface.name().unwrap()).unwrap();
+        assert_eq!(reader.frame(), 0);
+
+        surface.lock_pixels()[0] = 42;
+        // Not unlocked yet
+        assert!(reader.to_image().unwrap().is_none());
+        surface.unlock_pixels();
+        let image = reader.to_image().unwrap().unwrap();
+        assert_eq!(reader.frame(), 1);
+        assert_eq!((image.width, image.height, image.data[0]), (2, 2, 42));
+
+        surface.publish(ULIntRect {
+            left: 0,
+            top: 0,
+            right: 1,
+            bottom: 1,
+        });
+        let info = reader.info().unwrap();
+        assert_eq!((info.frame, info.dirty.right, info.dirty.bottom), (1, 1, 1));
+
+        surface.resize(8, 8);
+        // Not painted at the new size yet
+        assert!(reader.to_image().unwrap().is_none());
+        surface.lock_pixels();
+        surface.unlock_pixels();
+        let info = reader.info().unwrap();
+        assert_eq!((info.frame, info.width, info.stride), (2, 8, 32));
+        assert_eq!(reader.pixels().unwrap().len(), 8 * 32);
+
+        // A header describing more pixels than the segment holds
+        surface
+            .mapping
+            .header()
+            .height
+            .store(1 << 20, Ordering::Release);
+        let error = reader.info().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
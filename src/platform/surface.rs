@@ -0,0 +1,231 @@
+use std::any::TypeId;
+use std::ffi::c_void;
+use std::sync::Mutex;
+
+use ultralight_sys::{ulPlatformSetSurfaceDefinition, ULSurfaceDefinition};
+
+use crate::{Image, PixelFormat, Surface};
+
+/// Type of the [SurfaceDefinition] set with [set_surface_definition_impl], if any.
+static SURFACE_TYPE: Mutex<Option<TypeId>> = Mutex::new(None);
+
+/// A custom pixel buffer the library renders views into.
+///
+/// Instances are created by the library with [SurfaceDefinition::create] and owned by the glue,
+/// they are dropped when the library destroys the surface.
+/// Pixels are premultiplied BGRA 32-bit (8 bits per channel).
+pub trait SurfaceDefinition: 'static {
+    fn create(width: u32, height: u32) -> Self
+    where
+        Self: Sized;
+    /// Width (in pixels).
+    fn width(&self) -> u32;
+    /// Height (in pixels).
+    fn height(&self) -> u32;
+    /// Number of bytes between rows (usually width * 4)
+    fn row_bytes(&self) -> u32 {
+        self.width() * 4
+    }
+    /// Size in bytes.
+    fn size(&self) -> u64 {
+        self.row_bytes() as u64 * self.height() as u64
+    }
+    /// Lock the pixel buffer for reading/writing, it must be at least [SurfaceDefinition::size] bytes long.
+    /// The buffer must stay at the same address until [SurfaceDefinition::unlock_pixels] is called.
+    fn lock_pixels(&mut self) -> &mut [u8];
+    /// Unlock the pixel buffer.
+    fn unlock_pixels(&mut self) {}
+    /// Resize the pixel buffer to a certain width and height (both in pixels).
+    /// This is never called while pixels are locked.
+    fn resize(&mut self, width: u32, height: u32);
+}
+
+unsafe extern "C" fn create<T: SurfaceDefinition>(width: u32, height: u32) -> *mut c_void {
+    Box::into_raw(Box::new(T::create(width, height))) as *mut c_void
+}
+
+unsafe extern "C" fn destroy<T: SurfaceDefinition>(user_data: *mut c_void) {
+    drop(Box::from_raw(user_data as *mut T));
+}
+
+unsafe extern "C" fn get_width<T: SurfaceDefinition>(user_data: *mut c_void) -> u32 {
+    (*(user_data as *const T)).width()
+}
+
+unsafe extern "C" fn get_height<T: SurfaceDefinition>(user_data: *mut c_void) -> u32 {
+    (*(user_data as *const T)).height()
+}
+
+unsafe extern "C" fn get_row_bytes<T: SurfaceDefinition>(user_data: *mut c_void) -> u32 {
+    (*(user_data as *const T)).row_bytes()
+}
+
+unsafe extern "C" fn get_size<T: SurfaceDefinition>(user_data: *mut c_void) -> u64 {
+    (*(user_data as *const T)).size()
+}
+
+unsafe extern "C" fn lock_pixels<T: SurfaceDefinition>(user_data: *mut c_void) -> *mut c_void {
+    (*(user_data as *mut T)).lock_pixels().as_mut_ptr() as *mut c_void
+}
+
+unsafe extern "C" fn unlock_pixels<T: SurfaceDefinition>(user_data: *mut c_void) {
+    (*(user_data as *mut T)).unlock_pixels();
+}
+
+unsafe extern "C" fn resize<T: SurfaceDefinition>(user_data: *mut c_void, width: u32, height: u32) {
+    (*(user_data as *mut T)).resize(width, height);
+}
+
+/// Use `T` for all surfaces created from now on.
+/// The surfaces can then be accessed with [Surface::definition].
+pub fn set_surface_definition_impl<T: SurfaceDefinition>() {
+    set_surface_definition(ULSurfaceDefinition {
+        create: Some(create::<T>),
+        destroy: Some(destroy::<T>),
+        get_width: Some(get_width::<T>),
+        get_height: Some(get_height::<T>),
+        get_row_bytes: Some(get_row_bytes::<T>),
+        get_size: Some(get_size::<T>),
+        lock_pixels: Some(lock_pixels::<T>),
+        unlock_pixels: Some(unlock_pixels::<T>),
+        resize: Some(resize::<T>),
+    });
+    *SURFACE_TYPE.lock().unwrap() = Some(TypeId::of::<T>());
+}
+
+/// Set a custom Surface implementation.
+/// This can be used to wrap a platform-specific GPU texture, Windows DIB, macOS CGImage,
+/// or any other pixel buffer target for display on screen.
+/// By default, the library uses a bitmap surface for all surfaces but you can override this
+/// by providing your own surface definition here.
+pub fn set_surface_definition(surface_definition: ULSurfaceDefinition) {
+    *SURFACE_TYPE.lock().unwrap() = None;
+    unsafe {
+        ulPlatformSetSurfaceDefinition(surface_definition);
+    }
+}
+
+impl Surface {
+    /// Get the [SurfaceDefinition] backing this surface.
+    /// Returns `None` if `T` isn't the implementation set with [set_surface_definition_impl].
+    ///
+    /// # Safety
+    /// The instance is owned by the library, and every call to [View::surface](crate::View::surface)
+    /// gives a new wrapper of it. While the returned reference is alive, no reference from
+    /// [Surface::definition_mut] may exist for the same surface (through any wrapper),
+    /// and the renderer must not update, render nor resize the view.
+    pub unsafe fn definition<T: SurfaceDefinition>(&self) -> Option<&T> {
+        self.definition_ptr::<T>().map(|ptr| &*ptr)
+    }
+
+    /// Get the [SurfaceDefinition] backing this surface, mutably.
+    ///
+    /// # Safety
+    /// Same as [Surface::definition], and no other reference to the instance may exist
+    /// while the returned one is alive. This must never be called while pixels are locked.
+    pub unsafe fn definition_mut<T: SurfaceDefinition>(&mut self) -> Option<&mut T> {
+        self.definition_ptr::<T>().map(|ptr| &mut *ptr)
+    }
+
+    fn definition_ptr<T: SurfaceDefinition>(&self) -> Option<*mut T> {
+        let registered = *SURFACE_TYPE.lock().unwrap();
+        if self.raw.is_null() || registered != Some(TypeId::of::<T>()) {
+            return None;
+        }
+        let ptr = self.user_data() as *mut T;
+        if ptr.is_null() {
+            None
+        } else {
+            Some(ptr)
+        }
+    }
+}
+
+/// A [SurfaceDefinition] backed by a `Vec<u8>`, with rows tightly packed.
+///
+/// ```no_run
+/// # use ultralight_rs::platform::{self, VecSurface};
+/// # use ultralight_rs::View;
+/// # fn test(view: &View) {
+/// platform::set_surface_definition_impl::<VecSurface>();
+/// // Create the renderer and views, render...
+/// let surface = view.surface();
+/// // Safe : no other reference to the surface exists and the renderer is idle
+/// let image = unsafe { surface.definition::<VecSurface>() }.unwrap().to_image();
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VecSurface {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+impl VecSurface {
+    /// Premultiplied BGRA pixels.
+    pub fn pixels(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Copy the pixels to an owned image.
+    pub fn to_image(&self) -> Image {
+        Image {
+            width: self.width,
+            height: self.height,
+            stride: self.width * 4,
+            format: PixelFormat::Bgra8,
+            premultiplied: true,
+            data: self.data.clone(),
+        }
+    }
+}
+
+impl SurfaceDefinition for VecSurface {
+    fn create(width: u32, height: u32) -> Self {
+        VecSurface {
+            width,
+            height,
+            data: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn lock_pixels(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        *self = VecSurface::create(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vec_surface_glue() {
+        unsafe {
+            let surface = create::<VecSurface>(4, 2);
+            assert_eq!(get_width::<VecSurface>(surface), 4);
+            assert_eq!(get_row_bytes::<VecSurface>(surface), 16);
+            assert_eq!(get_size::<VecSurface>(surface), 32);
+
+            let pixels = lock_pixels::<VecSurface>(surface) as *mut u8;
+            *pixels.add(31) = 255;
+            unlock_pixels::<VecSurface>(surface);
+            assert_eq!((*(surface as *const VecSurface)).pixels()[31], 255);
+
+            resize::<VecSurface>(surface, 1, 1);
+            assert_eq!(get_size::<VecSurface>(surface), 4);
+            destroy::<VecSurface>(surface);
+        }
+    }
+}
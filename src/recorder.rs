@@ -0,0 +1,255 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::{Config, Image, ImageFormat, PixelFormat, Renderer, View};
+
+enum Sink {
+    Y4m {
+        writer: BufWriter<File>,
+        size: Option<(u32, u32)>,
+    },
+    PngSequence(PathBuf),
+}
+
+/// Record a CPU-rendered [View] at a fixed frame rate, as a Y4M video or a numbered PNG sequence.
+///
+/// Ultralight animations follow the wall clock, so frames are paced in real time.
+/// When rendering can't keep up, frames are repeated so the recording keeps the page timing.
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use ultralight_rs::{Headless, Recorder};
+/// let mut recorder = Recorder::y4m("animation.y4m", 30).unwrap();
+/// let mut headless = Headless::builder().build();
+/// headless.view.load_url("file:///animation.html");
+/// recorder
+///     .record(&headless.renderer, &headless.view, Duration::from_secs(5))
+///     .unwrap();
+/// recorder.finish().unwrap();
+/// ```
+pub struct Recorder {
+    fps: u32,
+    sink: Sink,
+    frames: u64,
+    start: Option<Instant>,
+}
+
+impl Recorder {
+    /// Record to a YUV4MPEG2 (4:4:4) stream, readable by ffmpeg and most video tools.
+    pub fn y4m<P: AsRef<Path>>(path: P, fps: u32) -> Result<Self> {
+        let writer = BufWriter::new(File::create(path)?);
+        Recorder::new(Sink::Y4m { writer, size: None }, fps)
+    }
+
+    /// Record to `frame_00000.png`, `frame_00001.png`, ... in a directory, created if needed.
+    pub fn png_sequence<P: AsRef<Path>>(directory: P, fps: u32) -> Result<Self> {
+        fs::create_dir_all(&directory)?;
+        Recorder::new(Sink::PngSequence(directory.as_ref().to_path_buf()), fps)
+    }
+
+    fn new(sink: Sink, fps: u32) -> Result<Self> {
+        if fps == 0 {
+            bail!("frame rate must be at least 1");
+        }
+        Ok(Recorder {
+            fps,
+            sink,
+            frames: 0,
+            start: None,
+        })
+    }
+
+    /// Time between two frames.
+    pub fn frame_interval(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.fps as f64)
+    }
+
+    /// Number of frames written so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Duration of the recording so far.
+    pub fn timestamp(&self) -> Duration {
+        Duration::from_secs_f64(self.frames as f64 / self.fps as f64)
+    }
+
+    /// Opt in to repainting animations and smooth scrolling once per recorded frame,
+    /// instead of the 60 Hz default. This must be called before creating the [Renderer]
+    /// with this config.
+    ///
+    /// This replaces the delays set with [Config::animation_timer_delay] and
+    /// [Config::scroll_timer_delay].
+    pub fn override_timer_delays(&self, config: &Config) {
+        let delay = self.frame_interval().as_secs_f64();
+        config.animation_timer_delay(delay);
+        config.scroll_timer_delay(delay);
+    }
+
+    /// Wait for the next frame, update and render, then write the view surface.
+    pub fn capture_frame(&mut self, renderer: &Renderer, view: &View) -> Result<()> {
+        let start = *self.start.get_or_insert_with(Instant::now);
+        let deadline = start + self.timestamp();
+        let now = Instant::now();
+        if deadline > now {
+            thread::sleep(deadline - now);
+        }
+
+        renderer.update();
+        renderer.render();
+        let image = view.screenshot().ok_or_else(|| {
+            anyhow!("the view has no surface, the GPU renderer can't be recorded")
+        })?;
+
+        // Fill every frame slot elapsed since the last one
+        let due = frames_due(start.elapsed(), self.fps);
+        for _ in 0..due.saturating_sub(self.frames).max(1) {
+            self.write_frame(&image)?;
+        }
+        Ok(())
+    }
+
+    /// Capture the frames of the given duration of recording.
+    pub fn record(&mut self, renderer: &Renderer, view: &View, duration: Duration) -> Result<()> {
+        let target = self.frames + frame_count(duration, self.fps);
+        while self.frames < target {
+            self.capture_frame(renderer, view)?;
+        }
+        Ok(())
+    }
+
+    fn write_frame(&mut self, image: &Image) -> Result<()> {
+        match &mut self.sink {
+            Sink::Y4m { writer, size } => {
+                match size {
+                    None => {
+                        writeln!(
+                            writer,
+                            "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C444",
+                            image.width, image.height, self.fps
+                        )?;
+                        *size = Some((image.width, image.height));
+                    }
+                    Some(size) if *size != (image.width, image.height) => {
+                        bail!(
+                            "view was resized from {}x{} to {}x{} during a Y4M recording",
+                            size.0,
+                            size.1,
+                            image.width,
+                            image.height
+                        );
+                    }
+                    _ => {}
+                }
+                writer.write_all(b"FRAME\n")?;
+                writer.write_all(&to_ycbcr444(image))?;
+            }
+            Sink::PngSequence(directory) => {
+                let path = directory.join(format!("frame_{:05}.png", self.frames));
+                fs::write(path, image.encode(ImageFormat::Png)?)?;
+            }
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Flush the recording to disk.
+    pub fn finish(mut self) -> Result<()> {
+        if let Sink::Y4m { writer, .. } = &mut self.sink {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Number of frames covering `duration`.
+fn frame_count(duration: Duration, fps: u32) -> u64 {
+    (duration.as_secs_f64() * fps as f64).ceil() as u64
+}
+
+/// Number of frames that should have been written `elapsed` after the first one.
+fn frames_due(elapsed: Duration, fps: u32) -> u64 {
+    (elapsed.as_secs_f64() * fps as f64) as u64 + 1
+}
+
+/// Convert to planar BT.601 (limited range) Y, Cb and Cr, composited over black.
+fn to_ycbcr444(image: &Image) -> Vec<u8> {
+    let plane = image.width as usize * image.height as usize;
+    let mut out = vec![0u8; plane * 3];
+    let (y_plane, chroma) = out.split_at_mut(plane);
+    let (cb_plane, cr_plane) = chroma.split_at_mut(plane);
+
+    let pixels = image.rows().flat_map(|row| row.chunks_exact(4));
+    for (i, pixel) in pixels.enumerate() {
+        let (mut r, g, mut b) = (pixel[0] as f32, pixel[1] as f32, pixel[2] as f32);
+        if image.format == PixelFormat::Bgra8 {
+            std::mem::swap(&mut r, &mut b);
+        }
+        let (r, g, b) = if image.premultiplied {
+            (r, g, b)
+        } else {
+            let alpha = pixel[3] as f32 / 255.0;
+            (r * alpha, g * alpha, b * alpha)
+        };
+        y_plane[i] = (16.0 + (65.481 * r + 128.553 * g + 24.966 * b) / 255.0).round() as u8;
+        cb_plane[i] = (128.0 + (-37.797 * r - 74.203 * g + 112.0 * b) / 255.0).round() as u8;
+        cr_plane[i] = (128.0 + (112.0 * r - 93.786 * g - 18.214 * b) / 255.0).round() as u8;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ycbcr_conversion() {
+        let image = Image {
+            width: 3,
+            height: 1,
+            stride: 12,
+            format: PixelFormat::Bgra8,
+            premultiplied: true,
+            data: vec![255, 255, 255, 255, 0, 0, 0, 255, 255, 0, 0, 255],
+        };
+        // White, black and blue
+        assert_eq!(
+            to_ycbcr444(&image),
+            vec![235, 16, 41, 128, 128, 240, 128, 128, 110]
+        );
+    }
+
+    #[test]
+    fn frame_timing() {
+        assert_eq!(frame_count(Duration::from_secs(5), 30), 150);
+        assert_eq!(frame_count(Duration::from_millis(1010), 10), 11);
+        assert_eq!(frames_due(Duration::ZERO, 30), 1);
+        // Rendering the second frame ended 3 intervals late, it is written 3 times
+        assert_eq!(frames_due(Duration::from_millis(300), 10), 4);
+
+        let directory = std::env::temp_dir().join(format!(
+            "ultralight-rs-recorder-test-{}",
+            std::process::id()
+        ));
+        let mut recorder = Recorder::png_sequence(&directory, 4).unwrap();
+        let image = Image {
+            width: 1,
+            height: 1,
+            stride: 4,
+            format: PixelFormat::Rgba8,
+            premultiplied: false,
+            data: vec![0, 0, 0, 255],
+        };
+        recorder.write_frame(&image).unwrap();
+        recorder.write_frame(&image).unwrap();
+        assert_eq!(recorder.frames(), 2);
+        assert_eq!(recorder.timestamp(), Duration::from_millis(500));
+        assert!(directory.join("frame_00001.png").exists());
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
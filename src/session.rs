@@ -12,17 +12,18 @@ pub struct Session {
 
 impl Session {
     /// Create a Session to store local data in (such as cookies, local storage, application cache, indexed db, etc).
-    pub fn new(renderer: Renderer, is_persistent: bool, name: &str) -> Self {
+    pub fn new(renderer: &Renderer, is_persistent: bool, name: &str) -> Self {
+        let name = ULString::from(name);
         unsafe {
             Session {
-                raw: ulCreateSession(renderer.raw, is_persistent, ULString::from(name).into()),
+                raw: ulCreateSession(renderer.raw, is_persistent, name.raw()),
                 created: true,
             }
         }
     }
 
     /// Get the default session (persistent session named "default").
-    pub fn default(renderer: Renderer) -> Self {
+    pub fn default(renderer: &Renderer) -> Self {
         unsafe {
             Session {
                 raw: ulDefaultSession(renderer.raw),
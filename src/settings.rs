@@ -28,7 +28,7 @@ impl Settings {
     pub fn developer_name(&self, developer_name: &str) {
         unsafe {
             let ulstr: ULString = developer_name.into();
-            ulSettingsSetDeveloperName(self.raw, ulstr.into());
+            ulSettingsSetDeveloperName(self.raw, ulstr.raw());
         }
     }
 
@@ -38,7 +38,7 @@ impl Settings {
     pub fn app_name(&self, app_name: &str) {
         unsafe {
             let ulstr: ULString = app_name.into();
-            ulSettingsSetAppName(self.raw, ulstr.into());
+            ulSettingsSetAppName(self.raw, ulstr.raw());
         }
     }
 
@@ -52,7 +52,7 @@ impl Settings {
     pub fn file_system_path(&self, file_system_path: &str) {
         unsafe {
             let ulstr: ULString = file_system_path.into();
-            ulSettingsSetFileSystemPath(self.raw, ulstr.into());
+            ulSettingsSetFileSystemPath(self.raw, ulstr.raw());
         }
     }
 
@@ -49,6 +49,28 @@ impl ULString {
         }
     }
 
+    /// Copy the string to Rust, replacing invalid UTF-16 (eg lone surrogates from JavaScript)
+    /// with the replacement character.
+    ///
+    /// The platform callbacks convert their strings with this, a panic in them can't unwind
+    /// into the library and aborts the process.
+    pub fn to_string_lossy(&self) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+        unsafe {
+            String::from_utf16_lossy(std::slice::from_raw_parts(
+                ulStringGetData(self.raw),
+                ulStringGetLength(self.raw) as usize,
+            ))
+        }
+    }
+
+    /// Get the underlying handle, only valid for as long as this ULString is alive.
+    pub(crate) fn raw(&self) -> ultralight_sys::ULString {
+        self.raw
+    }
+
     pub fn set(&mut self, other: &ULString) {
         unsafe {
             ulStringAssignString(self.raw, other.raw);
@@ -90,12 +112,6 @@ impl Into<String> for ULString {
     }
 }
 
-impl Into<ultralight_sys::ULString> for ULString {
-    fn into(self) -> ultralight_sys::ULString {
-        self.raw
-    }
-}
-
 impl Drop for ULString {
     fn drop(&mut self) {
         unsafe {
@@ -1,18 +1,19 @@
 use std::os::raw::c_void;
 use std::ptr::null_mut;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 use ultralight_sys::{
     ulCreateScrollEvent, ulCreateView, ulDestroyScrollEvent, ulDestroyView,
-    ulViewCreateInspectorView, ulViewFireScrollEvent, ulViewGetHeight, ulViewGetNeedsPaint,
-    ulViewGetRenderTarget, ulViewGetSurface, ulViewGetTitle, ulViewGetURL, ulViewGetWidth,
-    ulViewLoadHTML, ulViewLoadURL, ulViewLockJSContext, ulViewReload, ulViewResize,
-    ulViewSetAddConsoleMessageCallback, ulViewSetBeginLoadingCallback,
+    ulViewCreateInspectorView, ulViewFireKeyEvent, ulViewFireMouseEvent, ulViewFireScrollEvent,
+    ulViewFocus, ulViewGetHeight, ulViewGetNeedsPaint, ulViewGetRenderTarget, ulViewGetSurface,
+    ulViewGetTitle, ulViewGetURL, ulViewGetWidth, ulViewHasFocus, ulViewHasInputFocus,
+    ulViewIsLoading, ulViewLoadHTML, ulViewLoadURL, ulViewLockJSContext, ulViewReload,
+    ulViewResize, ulViewSetAddConsoleMessageCallback, ulViewSetBeginLoadingCallback,
     ulViewSetChangeCursorCallback, ulViewSetChangeTitleCallback, ulViewSetChangeTooltipCallback,
     ulViewSetChangeURLCallback, ulViewSetCreateChildViewCallback, ulViewSetDOMReadyCallback,
     ulViewSetFailLoadingCallback, ulViewSetFinishLoadingCallback, ulViewSetNeedsPaint,
-    ulViewSetUpdateHistoryCallback, ulViewSetWindowObjectReadyCallback, ulViewStop,
+    ulViewSetUpdateHistoryCallback, ulViewSetWindowObjectReadyCallback, ulViewStop, ulViewUnfocus,
     ulViewUnlockJSContext, JSContextGetGlobalObject, JSContextRef, JSEvaluateScript, JSValueRef,
     ULIntRect, ULRenderTarget, ULScrollEventType, ULView,
 };
@@ -22,7 +23,9 @@ use crate::internal::{
     unpack_closure_view_cursor, unpack_closure_view_fail_loading, unpack_closure_view_history,
 };
 use crate::jsc::{JSString, JSValue};
-use crate::{Cursor, Renderer, Session, Surface, ULString};
+use crate::platform;
+use crate::wait;
+use crate::{Cursor, Image, KeyEvent, MouseEvent, Renderer, Session, Surface, ULString};
 
 pub struct View {
     pub(crate) raw: ULView,
@@ -88,17 +91,60 @@ impl View {
         unsafe { ulViewGetSurface(self.raw).into() }
     }
 
+    /// Copy the pixels of the Surface to an owned buffer.
+    /// Returns None when the GPU renderer is enabled, as views don't have a Surface then :
+    ///
+    /// ```no_run
+    /// # use ultralight_rs::{Config, Renderer, Session, View};
+    /// let config = Config::new();
+    /// config.use_gpu_renderer(true);
+    /// let renderer = Renderer::new(&config);
+    /// let view = View::new(&renderer, 800, 600, false, &Session::default(&renderer), false);
+    /// assert!(view.screenshot().is_none());
+    /// ```
+    ///
+    /// This is only valid for the default Surface implementation.
+    pub fn screenshot(&self) -> Option<Image> {
+        let surface = self.surface();
+        if surface.raw.is_null() {
+            return None;
+        }
+        let (width, height) = (surface.width() as i32, surface.height() as i32);
+        Some(Image::from_surface(
+            &surface,
+            ULIntRect {
+                left: 0,
+                top: 0,
+                right: width,
+                bottom: height,
+            },
+        ))
+    }
+
+    /// Copy a region of the Surface to an owned buffer, the region is clamped to the view bounds.
+    /// Returns None when the GPU renderer is enabled.
+    pub fn screenshot_rect(&self, rect: ULIntRect) -> Option<Image> {
+        let surface = self.surface();
+        if surface.raw.is_null() {
+            None
+        } else {
+            Some(Image::from_surface(&surface, rect))
+        }
+    }
+
     /// Load a raw string of HTML.
     pub fn load_html(&self, html: &str) {
         unsafe {
-            ulViewLoadHTML(self.raw, ULString::from(html).into());
+            let html = ULString::from(html);
+            ulViewLoadHTML(self.raw, html.raw());
         }
     }
 
     /// Load a URL into main frame.
     pub fn load_url(&self, url: &str) {
         unsafe {
-            ulViewLoadURL(self.raw, ULString::from(url).into());
+            let url = ULString::from(url);
+            ulViewLoadURL(self.raw, url.raw());
         }
     }
 
@@ -129,6 +175,11 @@ impl View {
         unsafe { ulViewGetURL(self.raw).into() }
     }
 
+    /// Whether or not the main frame is loading.
+    pub fn is_loading(&self) -> bool {
+        unsafe { ulViewIsLoading(self.raw) }
+    }
+
     /// Whether or not a view should be painted during the next call to ulRender.
     pub fn needs_repaint(&self) -> bool {
         unsafe { ulViewGetNeedsPaint(self.raw) }
@@ -158,25 +209,89 @@ impl View {
         }
     }
 
+    /// Fire a keyboard event.
+    pub fn fire_key_event(&mut self, event: &KeyEvent) {
+        unsafe {
+            let key_event = event.create();
+
+            ulViewFireKeyEvent(self.raw, key_event);
+
+            KeyEvent::destroy(key_event);
+        }
+    }
+
+    /// Fire a mouse event.
+    pub fn fire_mouse_event(&mut self, event: &MouseEvent) {
+        unsafe {
+            let mouse_event = event.create();
+
+            ulViewFireMouseEvent(self.raw, mouse_event);
+
+            MouseEvent::destroy(mouse_event);
+        }
+    }
+
+    /// Give focus to the View.
+    /// You should call this to give visual indication that the View has input focus
+    /// (changes active text selection colors, for example).
+    pub fn focus(&mut self) {
+        unsafe {
+            ulViewFocus(self.raw);
+        }
+    }
+
+    /// Remove focus from the View and unfocus any focused input elements.
+    pub fn unfocus(&mut self) {
+        unsafe {
+            ulViewUnfocus(self.raw);
+        }
+    }
+
+    /// Whether or not the View has focus.
+    pub fn has_focus(&self) -> bool {
+        unsafe { ulViewHasFocus(self.raw) }
+    }
+
+    /// Whether or not the View has an input element with visible keyboard focus
+    /// (indicated by a blinking caret).
+    pub fn has_input_focus(&self) -> bool {
+        unsafe { ulViewHasInputFocus(self.raw) }
+    }
+
     pub fn get_scroll_height(&mut self) -> Result<f64> {
         self.evaluate_script("document.body.scrollHeight")
             .map(|v| v.as_number().unwrap())
     }
 
     /// Evaluates a string of JavaScript.
+    /// Returns an error containing the exception message if the script throws.
+    ///
+    /// Breaking change : exceptions used to be ignored, returning an `undefined` value.
+    /// Use `.ok()` to keep ignoring them.
     ///
     /// - `script` A JSString containing the script to evaluate.
     pub fn evaluate_script(&mut self, script: &str) -> Result<JSValue> {
         unsafe {
             let jsctx = self.lock_js_ctx();
+            let mut exception: JSValueRef = null_mut();
             let result: JSValueRef = JSEvaluateScript(
                 jsctx.ctx,
                 JSString::from(script).raw,
                 null_mut(),
                 null_mut(),
                 0,
-                null_mut(),
+                &mut exception,
             );
+            if !exception.is_null() {
+                let exception = JSValue {
+                    raw: exception,
+                    ctx: jsctx.ctx,
+                };
+                return Err(match exception.as_string() {
+                    Ok(message) => anyhow!("{}", message.to_string()),
+                    Err(_) => anyhow!("uncaught JavaScript exception"),
+                });
+            }
             Ok(JSValue {
                 raw: result,
                 ctx: jsctx.ctx,
@@ -194,6 +309,23 @@ impl View {
         }
     }
 
+    /// Send the console messages of this view to the logger set with
+    /// [platform::set_logger_impl](crate::platform::set_logger_impl), tagged with `tag`
+    /// so the messages of several views can be told apart.
+    ///
+    /// A view has a single console message callback : this replaces the one set by
+    /// [View::enable_default_logger], and calling `enable_default_logger` afterwards replaces this.
+    pub fn forward_console_messages(&mut self, tag: &str) {
+        platform::set_view_tag(self.raw, tag);
+        unsafe {
+            ulViewSetAddConsoleMessageCallback(
+                self.raw,
+                Some(platform::console_message_cb),
+                std::ptr::null_mut() as *mut c_void,
+            );
+        }
+    }
+
     pub fn use_js_ctx<F, R>(&self, consumer: F) -> R
     where
         F: Fn(ultralight_sys::JSContextRef, ultralight_sys::JSObjectRef) -> R,
@@ -241,6 +373,7 @@ impl View {
         unsafe {
             let (user_data, callback) = unpack_closure_view_0(cb);
             ulViewSetFinishLoadingCallback(self.raw, Some(callback), user_data);
+            wait::set_finish_loading_callback(self.raw, callback, user_data);
         }
     }
 
@@ -351,6 +484,7 @@ impl View {
         unsafe {
             let (user_data, callback) = unpack_closure_view_fail_loading(cb);
             ulViewSetFailLoadingCallback(self.raw, Some(callback), user_data);
+            wait::set_fail_loading_callback(self.raw, callback, user_data);
         }
     }
 
@@ -379,6 +513,8 @@ impl Drop for View {
     fn drop(&mut self) {
         unsafe {
             if self.created {
+                platform::remove_view_tag(self.raw);
+                wait::remove_load_callbacks(self.raw);
                 ulDestroyView(self.raw)
             }
         }
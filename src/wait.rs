@@ -0,0 +1,511 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::ffi::c_void;
+use std::fmt;
+use std::future::Future;
+use std::os::raw::{c_int, c_ulonglong};
+use std::pin::Pin;
+use std::ptr::null_mut;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ultralight_sys::{ulViewSetFailLoadingCallback, ulViewSetFinishLoadingCallback, ULView};
+
+use crate::helpers::js_string_literal;
+use crate::{Renderer, ULString, View};
+
+/// Time to sleep between two renderer updates while waiting on a page.
+const POLL_INTERVAL: Duration = Duration::from_millis(4);
+
+/// Error returned when waiting for a page load.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadError {
+    /// The main frame failed to load.
+    Failed {
+        url: String,
+        description: String,
+        error_domain: String,
+        error_code: i32,
+    },
+    /// The main frame did not finish loading before the timeout expired.
+    Timeout,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Failed {
+                url,
+                description,
+                error_domain,
+                error_code,
+            } => write!(
+                f,
+                "failed to load {} : {} ({} {})",
+                url, description, error_domain, error_code
+            ),
+            LoadError::Timeout => write!(f, "timed out while waiting for page load"),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+/// A condition waited upon by one of the `View::wait_for_*` functions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WaitCondition {
+    /// An element matching this CSS selector exists in the document.
+    Selector(String),
+    /// This JavaScript predicate evaluates to a truthy value.
+    Function(String),
+    /// No page load is pending and the view doesn't need to be repainted.
+    Idle,
+}
+
+impl fmt::Display for WaitCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WaitCondition::Selector(selector) => write!(f, "selector `{}`", selector),
+            WaitCondition::Function(predicate) => write!(f, "function `{}`", predicate),
+            WaitCondition::Idle => write!(f, "idle"),
+        }
+    }
+}
+
+/// Error returned by the `View::wait_for_*` functions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WaitError {
+    /// The condition was still unmet when the timeout expired.
+    Timeout(WaitCondition),
+    /// The JavaScript used to check the condition was still throwing an exception
+    /// when the timeout expired.
+    Script(WaitCondition, String),
+}
+
+impl fmt::Display for WaitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WaitError::Timeout(condition) => write!(f, "timed out while waiting for {}", condition),
+            WaitError::Script(condition, message) => write!(
+                f,
+                "script error while waiting for {} : {}",
+                condition, message
+            ),
+        }
+    }
+}
+
+impl Error for WaitError {}
+
+type FinishLoadingCallback =
+    unsafe extern "C" fn(*mut c_void, ULView, c_ulonglong, bool, ultralight_sys::ULString);
+type FailLoadingCallback = unsafe extern "C" fn(
+    *mut c_void,
+    ULView,
+    c_ulonglong,
+    bool,
+    ultralight_sys::ULString,
+    ultralight_sys::ULString,
+    ultralight_sys::ULString,
+    c_int,
+);
+
+/// Load callbacks set with [View::on_finish_loading] and [View::on_fail_loading],
+/// with their user data as an address.
+#[derive(Default, Copy, Clone)]
+struct LoadCallbacks {
+    finish: Option<(FinishLoadingCallback, usize)>,
+    fail: Option<(FailLoadingCallback, usize)>,
+}
+
+/// User load callbacks by raw view, chained to and restored by the load helpers.
+static LOAD_CALLBACKS: Mutex<BTreeMap<usize, LoadCallbacks>> = Mutex::new(BTreeMap::new());
+
+pub(crate) fn set_finish_loading_callback(
+    view: ULView,
+    callback: FinishLoadingCallback,
+    user_data: *mut c_void,
+) {
+    let mut callbacks = LOAD_CALLBACKS.lock().unwrap();
+    callbacks.entry(view as usize).or_default().finish = Some((callback, user_data as usize));
+}
+
+pub(crate) fn set_fail_loading_callback(
+    view: ULView,
+    callback: FailLoadingCallback,
+    user_data: *mut c_void,
+) {
+    let mut callbacks = LOAD_CALLBACKS.lock().unwrap();
+    callbacks.entry(view as usize).or_default().fail = Some((callback, user_data as usize));
+}
+
+pub(crate) fn remove_load_callbacks(view: ULView) {
+    LOAD_CALLBACKS.lock().unwrap().remove(&(view as usize));
+}
+
+/// Outcome of the main frame load, written to by the view callbacks.
+struct LoadState {
+    result: Option<Result<(), LoadError>>,
+    /// User callbacks, called from ours.
+    user: LoadCallbacks,
+}
+
+unsafe extern "C" fn finish_loading_cb(
+    user_data: *mut c_void,
+    caller: ULView,
+    frame_id: c_ulonglong,
+    is_main_frame: bool,
+    url: ultralight_sys::ULString,
+) {
+    let state = &mut *(user_data as *mut LoadState);
+    if is_main_frame && state.result.is_none() {
+        state.result = Some(Ok(()));
+    }
+    if let Some((callback, data)) = state.user.finish {
+        callback(data as *mut c_void, caller, frame_id, is_main_frame, url);
+    }
+}
+
+unsafe extern "C" fn fail_loading_cb(
+    user_data: *mut c_void,
+    caller: ULView,
+    frame_id: c_ulonglong,
+    is_main_frame: bool,
+    url: ultralight_sys::ULString,
+    description: ultralight_sys::ULString,
+    error_domain: ultralight_sys::ULString,
+    error_code: c_int,
+) {
+    let state = &mut *(user_data as *mut LoadState);
+    if is_main_frame && state.result.is_none() {
+        state.result = Some(Err(LoadError::Failed {
+            url: ULString::from(url).to_string_lossy(),
+            description: ULString::from(description).to_string_lossy(),
+            error_domain: ULString::from(error_domain).to_string_lossy(),
+            error_code,
+        }));
+    }
+    if let Some((callback, data)) = state.user.fail {
+        callback(
+            data as *mut c_void,
+            caller,
+            frame_id,
+            is_main_frame,
+            url,
+            description,
+            error_domain,
+            error_code,
+        );
+    }
+}
+
+/// Install our load callbacks on the view, pointing to a new [LoadState].
+/// The user callbacks keep being called until [unwatch_load] restores them.
+///
+/// The state is only accessed through the returned pointer, since the view callbacks
+/// write to it too, until [unwatch_load] frees it.
+unsafe fn watch_load(view: &View) -> *mut LoadState {
+    let state = Box::into_raw(Box::new(LoadState {
+        result: None,
+        user: LOAD_CALLBACKS
+            .lock()
+            .unwrap()
+            .get(&(view.raw as usize))
+            .copied()
+            .unwrap_or_default(),
+    }));
+    ulViewSetFinishLoadingCallback(view.raw, Some(finish_loading_cb), state as *mut c_void);
+    ulViewSetFailLoadingCallback(view.raw, Some(fail_loading_cb), state as *mut c_void);
+    state
+}
+
+/// Take the load outcome written by the view callbacks, if any.
+unsafe fn take_load_result(state: *mut LoadState) -> Option<Result<(), LoadError>> {
+    (*state).result.take()
+}
+
+/// Put back the user callbacks replaced by [watch_load] and free `state`.
+unsafe fn unwatch_load(view: &View, state: *mut LoadState) {
+    let state = Box::from_raw(state);
+    match state.user.finish {
+        Some((callback, data)) => {
+            ulViewSetFinishLoadingCallback(view.raw, Some(callback), data as *mut c_void)
+        }
+        None => ulViewSetFinishLoadingCallback(view.raw, None, null_mut()),
+    }
+    match state.user.fail {
+        Some((callback, data)) => {
+            ulViewSetFailLoadingCallback(view.raw, Some(callback), data as *mut c_void)
+        }
+        None => ulViewSetFailLoadingCallback(view.raw, None, null_mut()),
+    }
+}
+
+impl View {
+    /// Load a URL into main frame and drive the renderer until it finishes loading, fails
+    /// or `timeout` expires.
+    ///
+    /// Callbacks set with [View::on_finish_loading] or [View::on_fail_loading] are still called.
+    pub fn load_url_and_wait(
+        &mut self,
+        renderer: &Renderer,
+        url: &str,
+        timeout: Duration,
+    ) -> Result<(), LoadError> {
+        self.wait_for_load(renderer, timeout, |view| view.load_url(url))
+    }
+
+    /// Load a raw string of HTML and drive the renderer until it finishes loading, fails
+    /// or `timeout` expires.
+    ///
+    /// Callbacks set with [View::on_finish_loading] or [View::on_fail_loading] are still called.
+    pub fn load_html_and_wait(
+        &mut self,
+        renderer: &Renderer,
+        html: &str,
+        timeout: Duration,
+    ) -> Result<(), LoadError> {
+        self.wait_for_load(renderer, timeout, |view| view.load_html(html))
+    }
+
+    /// Async version of [View::load_url_and_wait].
+    ///
+    /// The load starts on the first poll of the returned future, which then updates the renderer
+    /// each time it is polled. It is meant to be driven by a single-threaded executor
+    /// on the renderer thread.
+    pub fn load_url_async<'a>(
+        &'a mut self,
+        renderer: &'a Renderer,
+        url: &str,
+        timeout: Duration,
+    ) -> LoadFuture<'a> {
+        LoadFuture::new(self, renderer, timeout, LoadRequest::Url(url.to_string()))
+    }
+
+    /// Async version of [View::load_html_and_wait].
+    pub fn load_html_async<'a>(
+        &'a mut self,
+        renderer: &'a Renderer,
+        html: &str,
+        timeout: Duration,
+    ) -> LoadFuture<'a> {
+        LoadFuture::new(self, renderer, timeout, LoadRequest::Html(html.to_string()))
+    }
+
+    /// Drive the renderer until an element matching `selector` exists in the document.
+    pub fn wait_for_selector(
+        &mut self,
+        renderer: &Renderer,
+        selector: &str,
+        timeout: Duration,
+    ) -> Result<(), WaitError> {
+        let script = format!(
+            "document.querySelector({}) !== null",
+            js_string_literal(selector)
+        );
+        self.wait_until(
+            renderer,
+            timeout,
+            WaitCondition::Selector(selector.to_string()),
+            |view| view.check_script(&script),
+        )
+    }
+
+    /// Drive the renderer until `predicate` evaluates to a truthy value.
+    ///
+    /// `predicate` can either be an expression or a function taking no arguments.
+    /// Exceptions thrown by `predicate` count as falsy, eg while the page is still defining
+    /// the objects it uses, [WaitError::Script] is returned if it still throws at the timeout.
+    pub fn wait_for_function(
+        &mut self,
+        renderer: &Renderer,
+        predicate: &str,
+        timeout: Duration,
+    ) -> Result<(), WaitError> {
+        let script = format!(
+            "(function() {{ var r = ({}); return !!(typeof r === 'function' ? r() : r); }})()",
+            predicate
+        );
+        self.wait_until(
+            renderer,
+            timeout,
+            WaitCondition::Function(predicate.to_string()),
+            |view| view.check_script(&script),
+        )
+    }
+
+    /// Drive the renderer until no page load is pending and no repaint is requested.
+    pub fn wait_for_idle(
+        &mut self,
+        renderer: &Renderer,
+        timeout: Duration,
+    ) -> Result<(), WaitError> {
+        self.wait_until(renderer, timeout, WaitCondition::Idle, |view| {
+            Ok(!view.is_loading() && !view.needs_repaint())
+        })
+    }
+
+    fn check_script(&mut self, script: &str) -> Result<bool, String> {
+        self.evaluate_script(script)
+            .map(|value| value.as_boolean())
+            .map_err(|e| e.to_string())
+    }
+
+    /// Update and render until `check` returns true, checking between the update and the render.
+    /// Script errors are retried, the last one is returned if the timeout expires right after it.
+    fn wait_until<F>(
+        &mut self,
+        renderer: &Renderer,
+        timeout: Duration,
+        condition: WaitCondition,
+        mut check: F,
+    ) -> Result<(), WaitError>
+    where
+        F: FnMut(&mut View) -> Result<bool, String>,
+    {
+        let deadline = Instant::now() + timeout;
+        loop {
+            renderer.update();
+            // Scripts can throw until the page defines what they use, keep polling
+            let checked = check(self);
+            renderer.render();
+            match checked {
+                Ok(true) => return Ok(()),
+                Err(message) if Instant::now() >= deadline => {
+                    return Err(WaitError::Script(condition, message))
+                }
+                _ if Instant::now() >= deadline => return Err(WaitError::Timeout(condition)),
+                _ => {}
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn wait_for_load<F>(
+        &mut self,
+        renderer: &Renderer,
+        timeout: Duration,
+        load: F,
+    ) -> Result<(), LoadError>
+    where
+        F: FnOnce(&View),
+    {
+        let deadline = Instant::now() + timeout;
+        let state = unsafe { watch_load(self) };
+        load(self);
+
+        let result = loop {
+            renderer.update();
+            if let Some(result) = unsafe { take_load_result(state) } {
+                break result;
+            }
+            if Instant::now() >= deadline {
+                self.stop();
+                break Err(LoadError::Timeout);
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+
+        unsafe {
+            unwatch_load(self, state);
+        }
+        result
+    }
+}
+
+enum LoadRequest {
+    Url(String),
+    Html(String),
+}
+
+enum LoadStage {
+    /// Not polled yet.
+    Pending(LoadRequest),
+    Loading {
+        deadline: Instant,
+    },
+    Done(Result<(), LoadError>),
+}
+
+/// Future returned by [View::load_url_async] and [View::load_html_async].
+pub struct LoadFuture<'a> {
+    view: &'a View,
+    renderer: &'a Renderer,
+    timeout: Duration,
+    // Allocated by [watch_load] on the first poll, so it stays valid when the future moves.
+    state: *mut LoadState,
+    stage: LoadStage,
+}
+
+impl<'a> LoadFuture<'a> {
+    fn new(
+        view: &'a mut View,
+        renderer: &'a Renderer,
+        timeout: Duration,
+        request: LoadRequest,
+    ) -> Self {
+        LoadFuture {
+            view,
+            renderer,
+            timeout,
+            state: null_mut(),
+            stage: LoadStage::Pending(request),
+        }
+    }
+
+    fn finish(&mut self, result: Result<(), LoadError>) -> Poll<Result<(), LoadError>> {
+        unsafe {
+            unwatch_load(self.view, self.state);
+        }
+        self.state = null_mut();
+        self.stage = LoadStage::Done(result.clone());
+        Poll::Ready(result)
+    }
+}
+
+impl Future for LoadFuture<'_> {
+    type Output = Result<(), LoadError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        match &this.stage {
+            LoadStage::Done(result) => return Poll::Ready(result.clone()),
+            LoadStage::Pending(request) => {
+                this.state = unsafe { watch_load(this.view) };
+                match request {
+                    LoadRequest::Url(url) => this.view.load_url(url),
+                    LoadRequest::Html(html) => this.view.load_html(html),
+                }
+                this.stage = LoadStage::Loading {
+                    deadline: Instant::now() + this.timeout,
+                };
+            }
+            LoadStage::Loading { .. } => {}
+        }
+
+        this.renderer.update();
+        if let Some(result) = unsafe { take_load_result(this.state) } {
+            return this.finish(result);
+        }
+        if matches!(this.stage, LoadStage::Loading { deadline } if Instant::now() >= deadline) {
+            this.view.stop();
+            return this.finish(Err(LoadError::Timeout));
+        }
+
+        // Nothing wakes us up from the engine side, ask to be polled again.
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+impl Drop for LoadFuture<'_> {
+    fn drop(&mut self) {
+        if let LoadStage::Loading { .. } = self.stage {
+            unsafe {
+                unwatch_load(self.view, self.state);
+            }
+        }
+    }
+}
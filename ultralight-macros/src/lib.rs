@@ -0,0 +1,107 @@
+//! Procedural macros for `ultralight-rs`, use them through the main crate.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use proc_macro::{Delimiter, TokenStream, TokenTree};
+
+/// Expand to a `&'static [(&'static str, &'static [u8])]` table of every file in a directory,
+/// relative to the crate manifest directory. Paths in the table are relative to that directory,
+/// with `/` separators, sorted.
+///
+/// Used by `ultralight_rs::include_assets!`.
+#[proc_macro]
+pub fn asset_table(input: TokenStream) -> TokenStream {
+    match expand(input) {
+        Ok(tokens) => tokens,
+        Err(message) => format!("compile_error!({:?})", message).parse().unwrap(),
+    }
+}
+
+fn expand(input: TokenStream) -> Result<TokenStream, String> {
+    let dir = parse_str_literal(input)?;
+    let manifest_dir =
+        env::var("CARGO_MANIFEST_DIR").map_err(|_| "CARGO_MANIFEST_DIR is not set".to_string())?;
+    let root = Path::new(&manifest_dir).join(&dir);
+    if !root.is_dir() {
+        return Err(format!("'{}' is not a directory", root.display()));
+    }
+
+    let mut files = Vec::new();
+    walk(&root, &mut files).map_err(|e| format!("can't list '{}' : {}", root.display(), e))?;
+    let mut entries: Vec<(String, PathBuf)> = files
+        .into_iter()
+        .map(|file| {
+            let relative = file
+                .strip_prefix(&root)
+                .unwrap()
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            (relative, file)
+        })
+        .collect();
+    entries.sort();
+
+    let mut table = String::from("&[");
+    for (path, file) in entries {
+        let file = file
+            .to_str()
+            .ok_or_else(|| format!("'{}' is not valid UTF-8", file.display()))?;
+        table.push_str(&format!(
+            "({:?}, include_bytes!({:?}) as &'static [u8]),",
+            path, file
+        ));
+    }
+    table.push(']');
+    table.parse().map_err(|e| format!("{:?}", e))
+}
+
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn parse_str_literal(input: TokenStream) -> Result<String, String> {
+    let mut tokens = input.into_iter();
+    let literal = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Literal(literal)), None) => literal.to_string(),
+        // Literals forwarded by macro_rules are wrapped in an invisible group
+        (Some(TokenTree::Group(group)), None) if group.delimiter() == Delimiter::None => {
+            return parse_str_literal(group.stream())
+        }
+        _ => return Err("expected a directory path string literal".to_string()),
+    };
+    if let Some(raw) = literal.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        return Ok(raw[hashes + 1..raw.len() - hashes - 1].to_string());
+    }
+    let quoted = literal
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .ok_or_else(|| "expected a directory path string literal".to_string())?;
+    let mut value = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some(c) => value.push(c),
+                None => {}
+            }
+        } else {
+            value.push(c);
+        }
+    }
+    Ok(value)
+}